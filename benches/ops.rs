@@ -0,0 +1,82 @@
+use std::{hint::black_box, time::Instant};
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use rbtree::{RbTree, Strategy};
+
+const N: usize = 200_000;
+const ROUNDS: usize = 5;
+
+// prints the best time of a few rounds, in nanoseconds per operation
+fn bench<S, F: FnMut(&mut S)>(name: &str, ops: usize, mut setup: impl FnMut() -> S, mut f: F) {
+    let mut best = f64::MAX;
+    for _ in 0..ROUNDS {
+        let mut state = setup();
+        let start = Instant::now();
+        f(&mut state);
+        let ns = start.elapsed().as_nanos() as f64 / ops as f64;
+        best = best.min(ns);
+    }
+    println!("{:<36} {:>8.1} ns/op", name, best);
+}
+
+fn filled(strategy: Strategy, vals: &[i32]) -> RbTree<i32> {
+    let mut t = RbTree::with_strategy(strategy);
+    for &v in vals {
+        t.add(v);
+    }
+    t
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let random: Vec<i32> = (0..N).map(|_| rng.gen()).collect();
+    let mut shuffled = random.clone();
+    shuffled.shuffle(&mut rng);
+    let ascending: Vec<i32> = (0..N as i32).collect();
+    let window = N / 10;
+
+    for strategy in [Strategy::BottomUp, Strategy::TopDown] {
+        bench(
+            &format!("add random {:?}", strategy),
+            N,
+            || RbTree::with_strategy(strategy),
+            |t| {
+                for &v in &random {
+                    t.add(v);
+                }
+            },
+        );
+        bench(
+            &format!("add ascending {:?}", strategy),
+            N,
+            || RbTree::with_strategy(strategy),
+            |t| {
+                for &v in &ascending {
+                    t.add(v);
+                }
+            },
+        );
+        bench(
+            &format!("remove random {:?}", strategy),
+            N,
+            || filled(strategy, &random),
+            |t| {
+                for v in &shuffled {
+                    black_box(t.remove(v));
+                }
+            },
+        );
+        // the size stays the same, every step adds a value and removes the oldest one
+        bench(
+            &format!("add + remove steady {:?}", strategy),
+            N - window,
+            || filled(strategy, &random[..window]),
+            |t| {
+                for i in window..N {
+                    t.add(random[i]);
+                    black_box(t.remove(&random[i - window]));
+                }
+            },
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use std::{collections::BTreeSet, hint::black_box, time::Instant};
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use rbtree::{AvlTree, OrderedSet, RbTree, SortedVec, Treap};
+
+// SortedVec shifts values on every change, a bigger N takes too long for it
+const N: usize = 50_000;
+const ROUNDS: usize = 3;
+
+// the best time of a few rounds, in nanoseconds per operation
+fn measure<S>(ops: usize, mut setup: impl FnMut() -> S, mut f: impl FnMut(&mut S)) -> f64 {
+    let mut best = f64::MAX;
+    for _ in 0..ROUNDS {
+        let mut state = setup();
+        let start = Instant::now();
+        f(&mut state);
+        let ns = start.elapsed().as_nanos() as f64 / ops as f64;
+        best = best.min(ns);
+    }
+    best
+}
+
+fn filled<S: OrderedSet<i32>>(new: &impl Fn() -> S, vals: &[i32]) -> S {
+    let mut set = new();
+    for &v in vals {
+        set.insert(v);
+    }
+    set
+}
+
+// one row of the table, every column is ns/op
+fn suite<S: OrderedSet<i32>>(name: &str, new: impl Fn() -> S, random: &[i32], shuffled: &[i32]) {
+    let insert = measure(N, &new, |s| {
+        for &v in random {
+            s.insert(v);
+        }
+    });
+    let ascending = measure(N, &new, |s| {
+        for v in 0..N as i32 {
+            s.insert(v);
+        }
+    });
+    let contains = measure(
+        N,
+        || filled(&new, random),
+        |s| {
+            for v in shuffled {
+                black_box(s.contains(v));
+            }
+        },
+    );
+    // short scans from random places, ns per scan
+    let range = measure(
+        N / 10,
+        || filled(&new, random),
+        |s| {
+            for &v in &shuffled[..N / 10] {
+                black_box(s.range(v..).take(10).sum::<i32>());
+            }
+        },
+    );
+    let remove = measure(
+        N,
+        || filled(&new, random),
+        |s| {
+            for v in shuffled {
+                black_box(s.remove(v));
+            }
+        },
+    );
+
+    println!(
+        "{:<10} {:>9.1} {:>9.1} {:>9.1} {:>9.1} {:>9.1}",
+        name, insert, ascending, contains, range, remove
+    );
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let random: Vec<i32> = (0..N).map(|_| rng.gen()).collect();
+    let mut shuffled = random.clone();
+    shuffled.shuffle(&mut rng);
+
+    println!(
+        "{:<10} {:>9} {:>9} {:>9} {:>9} {:>9}",
+        "ns/op", "insert", "ascending", "contains", "range", "remove"
+    );
+    suite("RbTree", RbTree::new, &random, &shuffled);
+    suite("AvlTree", AvlTree::new, &random, &shuffled);
+    suite("Treap", || Treap::with_seed(1), &random, &shuffled);
+    suite("SortedVec", SortedVec::new, &random, &shuffled);
+    suite("BTreeSet", BTreeSet::new, &random, &shuffled);
+}
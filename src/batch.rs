@@ -0,0 +1,174 @@
+use std::io::{self, BufRead, Write};
+
+use rbtree::RbTree;
+
+#[derive(Debug, PartialEq)]
+pub enum Op {
+    Add(i32),
+    Remove(i32),
+    // validate without changing the tree, the tree is validated after each step anyway
+    Check,
+    Print,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    Passed { steps: usize },
+    // the step which broke the tree, counted from 1
+    Broken { step: usize },
+    ParseError { line: usize },
+}
+
+// understands both shell-like commands and lines printed by tests with `PRINT_SEQ`
+// add 42
+// remove 42
+// check
+// t.add(42);
+// t.remove(&42);
+pub fn parse(line: &str) -> Result<Option<Op>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+        return Ok(None);
+    }
+
+    let (name, arg) = match line.strip_prefix("t.") {
+        Some(call) => {
+            let call = call.strip_suffix(';').unwrap_or(call).trim_end();
+            let (name, rest) = call
+                .split_once('(')
+                .ok_or_else(|| format!("`{}` is not a method call", line))?;
+            let arg = rest
+                .strip_suffix(')')
+                .ok_or_else(|| format!("`{}` has no closing bracket", line))?
+                .trim();
+            (name, arg.strip_prefix('&').unwrap_or(arg))
+        }
+        None => match line.split_once(char::is_whitespace) {
+            Some((name, arg)) => (name, arg.trim()),
+            None => (line, ""),
+        },
+    };
+
+    let value = || {
+        arg.parse::<i32>()
+            .map_err(|_| format!("`{}` needs a number, found `{}`", name, arg))
+    };
+    let op = match name {
+        "add" => Op::Add(value()?),
+        "remove" => Op::Remove(value()?),
+        "check" | "validate" | "is_valid" if arg.is_empty() => Op::Check,
+        "print" if arg.is_empty() => Op::Print,
+        _ => return Err(format!("unknown operation `{}`", line)),
+    };
+    Ok(Some(op))
+}
+
+// executes all operations on the tree and stops at the first broken step
+pub fn run<R: BufRead, W: Write>(
+    tree: &mut RbTree<i32>,
+    input: R,
+    mut out: W,
+) -> io::Result<Outcome> {
+    let mut steps = 0;
+
+    for (i, line) in input.lines().enumerate() {
+        let line = line?;
+        let op = match parse(&line) {
+            Ok(Some(op)) => op,
+            Ok(None) => continue,
+            Err(e) => {
+                writeln!(out, "line {}: {}", i + 1, e)?;
+                return Ok(Outcome::ParseError { line: i + 1 });
+            }
+        };
+        steps += 1;
+
+        match op {
+            Op::Add(v) => tree.add(v),
+            Op::Remove(v) => {
+                if !tree.remove(&v) {
+                    writeln!(out, "step {}: {} is not found", steps, v)?;
+                }
+            }
+            Op::Check => {}
+            Op::Print => write!(out, "{}", tree)?,
+        }
+
+        if let Err(violations) = tree.validate() {
+            writeln!(
+                out,
+                "step {} (line {}) broke the tree: {}",
+                steps,
+                i + 1,
+                line.trim()
+            )?;
+            for v in violations {
+                writeln!(out, "  {}", v)?;
+            }
+            write!(out, "{}", tree)?;
+            return Ok(Outcome::Broken { step: steps });
+        }
+    }
+
+    writeln!(out, "{} steps passed, len: {}", steps, tree.len())?;
+    Ok(Outcome::Passed { steps })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse, run, Op, Outcome};
+    use rbtree::{worst_case, Color, RbTree, SearchOptions};
+
+    #[test]
+    fn test_batch() {
+        assert_eq!(parse("t.add(42);"), Ok(Some(Op::Add(42))));
+        assert_eq!(parse("  t.remove(&-7); "), Ok(Some(Op::Remove(-7))));
+        assert_eq!(parse("remove 3"), Ok(Some(Op::Remove(3))));
+        assert_eq!(parse("check"), Ok(Some(Op::Check)));
+        assert_eq!(parse("// comment"), Ok(None));
+        assert!(parse("t.add(42").is_err());
+        assert!(parse("add x").is_err());
+        assert!(parse("check 1").is_err());
+
+        let script = "t.add(5);\nt.add(3);\n\n# comment\nadd 8\nremove 4\nt.remove(&3);\ncheck\n";
+        let mut t = RbTree::new();
+        let mut out = Vec::new();
+        let outcome = run(&mut t, script.as_bytes(), &mut out).unwrap();
+        assert_eq!(outcome, Outcome::Passed { steps: 6 });
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "step 4: 4 is not found\n6 steps passed, len: 2\n"
+        );
+
+        let mut out = Vec::new();
+        let outcome = run(&mut t, "add 1\nfly 2\n".as_bytes(), &mut out).unwrap();
+        assert_eq!(outcome, Outcome::ParseError { line: 2 });
+
+        // a tree broken from outside fails on the next step
+        t.root.as_ref().unwrap().borrow_mut().color = Color::Red;
+        let mut out = Vec::new();
+        let outcome = run(&mut t, "check\nadd 2\n".as_bytes(), &mut out).unwrap();
+        assert_eq!(outcome, Outcome::Broken { step: 1 });
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "step 1 (line 1) broke the tree: check\n  \
+             The root should be BLACK, see val: 5\n  \
+             Two consecutive RED nodes, see val: 1 at root/L\n  \
+             Two consecutive RED nodes, see val: 8 at root/R\n\
+             └─5r\n  ├─8r\n  └─1r\n"
+        );
+
+        // a found worst case is a ready file to run
+        let found = worst_case(4, &SearchOptions::default());
+        let mut t = RbTree::new();
+        let text = found.to_string();
+        let outcome = run(&mut t, text.as_bytes(), &mut Vec::new()).unwrap();
+        assert_eq!(
+            outcome,
+            Outcome::Passed {
+                steps: found.steps.len()
+            }
+        );
+        assert_eq!(t.to_brackets(), found.tree.to_brackets());
+    }
+}
@@ -0,0 +1,4 @@
+mod rb_tree;
+pub use rb_tree::*;
+mod ordered_set;
+pub use ordered_set::*;
@@ -1,26 +1,95 @@
-mod rb_tree;
-use rb_tree::RbTree;
-
-fn main() {
-    let mut t = RbTree::<i32>::new();
-
-    t.add(3);
-    t.add(5);
-    t.add(1);
-    t.add(7);
-    t.add(13);
-    t.add(15);
-    t.add(4);
-    t.add(17);
-    t.add(9);
-    t.add(11);
-    t.add(2);
-    t.add(21);
-
-    let v = 7;
-    let _ok = t.remove(&v);
-
-    t.print();
-
-    println!("RbTree len is: {}", t.len());
+mod batch;
+mod repl;
+
+use std::{
+    env, fs,
+    io::{self, BufReader},
+    process::ExitCode,
+};
+
+use rbtree::{worst_case, Goal, RbTree, SearchOptions, Strategy};
+
+const USAGE: &str = "\
+usage:
+  rbtree               start the interactive shell
+  rbtree run <file>    replay operations from the file, `-` reads stdin
+  rbtree search <height|black-height|rotations> <n> [--top-down] [--seed S] [--iterations K]
+                       find adds and removes which leave n values and make
+                       the tree the worst, prints a file for `rbtree run`";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    match args.as_slice() {
+        [] => {
+            println!("RbTree shell, type `help` to see commands");
+            match repl::Repl::new().run(io::stdin().lock(), io::stdout()) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(e) => fail(&e.to_string()),
+            }
+        }
+        ["run", path] => run(path),
+        ["search", goal, n, flags @ ..] => search(goal, n, flags),
+        _ => fail(USAGE),
+    }
+}
+
+fn run(path: &str) -> ExitCode {
+    let mut tree = RbTree::new();
+    let result = if path == "-" {
+        batch::run(&mut tree, io::stdin().lock(), io::stdout())
+    } else {
+        match fs::File::open(path) {
+            Ok(file) => batch::run(&mut tree, BufReader::new(file), io::stdout()),
+            Err(e) => return fail(&format!("can't open {}: {}", path, e)),
+        }
+    };
+
+    match result {
+        Ok(batch::Outcome::Passed { .. }) => ExitCode::SUCCESS,
+        Ok(_) => ExitCode::FAILURE,
+        Err(e) => fail(&e.to_string()),
+    }
+}
+
+fn search(goal: &str, n: &str, flags: &[&str]) -> ExitCode {
+    let goal = match goal {
+        "height" => Goal::Height,
+        "black-height" => Goal::BlackHeight,
+        "rotations" => Goal::Rotations,
+        _ => return fail(USAGE),
+    };
+    let n = match n.parse() {
+        Ok(n) => n,
+        Err(_) => return fail(&format!("`{}` is not a number", n)),
+    };
+
+    let mut options = SearchOptions {
+        goal,
+        ..SearchOptions::default()
+    };
+    let mut flags = flags.iter();
+    while let Some(flag) = flags.next() {
+        match *flag {
+            "--top-down" => options.strategy = Strategy::TopDown,
+            "--seed" => match flags.next().and_then(|v| v.parse().ok()) {
+                Some(seed) => options.seed = seed,
+                None => return fail("--seed needs a number"),
+            },
+            "--iterations" => match flags.next().and_then(|v| v.parse().ok()) {
+                Some(iterations) => options.iterations = iterations,
+                None => return fail("--iterations needs a number"),
+            },
+            _ => return fail(USAGE),
+        }
+    }
+
+    print!("{}", worst_case(n, &options));
+    ExitCode::SUCCESS
+}
+
+fn fail(message: &str) -> ExitCode {
+    eprintln!("{}", message);
+    ExitCode::from(2)
 }
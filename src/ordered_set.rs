@@ -0,0 +1,163 @@
+use std::{
+    collections::BTreeSet,
+    ops::{Bound, RangeBounds},
+};
+
+use crate::rb_tree::{after_start, before_end};
+use crate::RbTree;
+
+mod avl;
+pub use avl::AvlTree;
+mod sorted_vec;
+pub use sorted_vec::SortedVec;
+mod treap;
+pub use treap::Treap;
+mod tests;
+
+// a set of values kept in order, code written against it works with any
+// of the trees below and with `BTreeSet`
+//
+// values are returned by value, RbTree nodes are behind RefCell
+// and can't give out references
+pub trait OrderedSet<T> {
+    // false if the value is in the set already, the set is not changed then
+    fn insert(&mut self, val: T) -> bool;
+    fn remove(&mut self, val: &T) -> bool;
+    fn contains(&self, val: &T) -> bool;
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // values in ascending order
+    fn iter(&self) -> Box<dyn Iterator<Item = T> + '_>;
+    fn range<R: RangeBounds<T>>(&self, range: R) -> Box<dyn Iterator<Item = T> + '_>;
+}
+
+// RbTree keeps duplicates, as a set it adds only missing values
+impl<T> OrderedSet<T> for RbTree<T>
+where
+    T: std::fmt::Debug + std::cmp::Ord + std::cmp::Eq + std::fmt::Display + Clone,
+{
+    fn insert(&mut self, val: T) -> bool {
+        if RbTree::contains(self, &val) {
+            return false;
+        }
+        self.add(val);
+        true
+    }
+
+    fn remove(&mut self, val: &T) -> bool {
+        RbTree::remove(self, val)
+    }
+
+    fn contains(&self, val: &T) -> bool {
+        RbTree::contains(self, val)
+    }
+
+    fn len(&self) -> usize {
+        RbTree::len(self)
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = T> + '_> {
+        Box::new(RbTree::iter(self))
+    }
+
+    fn range<R: RangeBounds<T>>(&self, range: R) -> Box<dyn Iterator<Item = T> + '_> {
+        Box::new(RbTree::range(self, range))
+    }
+}
+
+impl<T: Ord + Clone> OrderedSet<T> for BTreeSet<T> {
+    fn insert(&mut self, val: T) -> bool {
+        BTreeSet::insert(self, val)
+    }
+
+    fn remove(&mut self, val: &T) -> bool {
+        BTreeSet::remove(self, val)
+    }
+
+    fn contains(&self, val: &T) -> bool {
+        BTreeSet::contains(self, val)
+    }
+
+    fn len(&self) -> usize {
+        BTreeSet::len(self)
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = T> + '_> {
+        Box::new(BTreeSet::iter(self).cloned())
+    }
+
+    fn range<R: RangeBounds<T>>(&self, range: R) -> Box<dyn Iterator<Item = T> + '_> {
+        // BTreeSet panics on a range which ends before it starts
+        let start = range.start_bound().cloned();
+        let end = range.end_bound().cloned();
+        let empty = match (start.as_ref(), end.as_ref()) {
+            (Bound::Included(s), Bound::Included(e)) => s > e,
+            (Bound::Included(s) | Bound::Excluded(s), Bound::Included(e) | Bound::Excluded(e)) => {
+                s >= e
+            }
+            _ => false,
+        };
+        if empty {
+            return Box::new(std::iter::empty());
+        }
+        Box::new(BTreeSet::range(self, (start, end)).cloned())
+    }
+}
+
+// trees with boxed nodes share the in-order walk
+pub(crate) trait BoxNode<T> {
+    fn val(&self) -> &T;
+    fn child(&self, pos: usize) -> Option<&Self>;
+}
+
+const LEFT: usize = 0;
+const RIGHT: usize = 1;
+
+pub(crate) struct NodeIter<'a, T, N> {
+    // the next node is on top, nodes below are its ancestors not visited yet
+    stack: Vec<&'a N>,
+    end: Bound<T>,
+}
+
+impl<'a, T: Ord + Clone, N: BoxNode<T>> NodeIter<'a, T, N> {
+    pub(crate) fn new<R: RangeBounds<T>>(root: Option<&'a N>, range: R) -> Self {
+        let mut iter = NodeIter {
+            stack: Vec::new(),
+            end: range.end_bound().cloned(),
+        };
+
+        let mut next = root;
+        while let Some(node) = next {
+            if after_start(node.val(), range.start_bound()) {
+                iter.stack.push(node);
+                next = node.child(LEFT);
+            } else {
+                next = node.child(RIGHT);
+            }
+        }
+        iter
+    }
+}
+
+impl<'a, T: Ord + 'a, N: BoxNode<T> + 'a> Iterator for NodeIter<'a, T, N> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let node = self.stack.pop()?;
+        if !before_end(node.val(), self.end.as_ref()) {
+            self.stack.clear();
+            return None;
+        }
+
+        let mut next = node.child(RIGHT);
+        while let Some(n) = next {
+            self.stack.push(n);
+            next = n.child(LEFT);
+        }
+        Some(node.val())
+    }
+}
@@ -0,0 +1,236 @@
+use std::{cmp::Ordering, ops::RangeBounds};
+
+use super::{BoxNode, NodeIter, OrderedSet, LEFT, RIGHT};
+
+type Link<T> = Option<Box<AvlNode<T>>>;
+
+struct AvlNode<T> {
+    val: T,
+    // number of nodes on the longest path down, a leaf has 1
+    height: u8,
+    children: [Link<T>; 2],
+}
+
+impl<T> AvlNode<T> {
+    fn update(&mut self) {
+        self.height = 1 + height(&self.children[LEFT]).max(height(&self.children[RIGHT]));
+    }
+
+    // left height minus right height, AVL rule keeps it in -1..=1
+    fn balance(&self) -> i16 {
+        height(&self.children[LEFT]) as i16 - height(&self.children[RIGHT]) as i16
+    }
+}
+
+impl<T> BoxNode<T> for AvlNode<T> {
+    fn val(&self) -> &T {
+        &self.val
+    }
+
+    fn child(&self, pos: usize) -> Option<&Self> {
+        self.children[pos].as_deref()
+    }
+}
+
+fn height<T>(link: &Link<T>) -> u8 {
+    link.as_ref().map_or(0, |n| n.height)
+}
+
+// AVL tree, heights of left and right subtrees differ at most by one,
+// it's stricter than RbTree, so it's lower but rotates more
+//
+// functions are recursive, the height is about 1.44*log2(n) at most
+pub struct AvlTree<T> {
+    root: Link<T>,
+    len: usize,
+}
+
+impl<T> Default for AvlTree<T> {
+    fn default() -> Self {
+        AvlTree { root: None, len: 0 }
+    }
+}
+
+impl<T: Ord + Clone> AvlTree<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn height(&self) -> usize {
+        height(&self.root) as usize
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        NodeIter::new(self.root.as_deref(), ..)
+    }
+
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> impl Iterator<Item = &T> + '_ {
+        NodeIter::new(self.root.as_deref(), range)
+    }
+
+    // checks heights and balance of every node and the order of values
+    pub fn is_valid(&self) -> bool {
+        fn check<T: Ord>(link: &Link<T>, min: Option<&T>, max: Option<&T>) -> Option<u8> {
+            let node = match link {
+                Some(n) => n,
+                None => return Some(0),
+            };
+            if min.is_some_and(|m| node.val <= *m) || max.is_some_and(|m| node.val >= *m) {
+                return None;
+            }
+            let left = check(&node.children[LEFT], min, Some(&node.val))?;
+            let right = check(&node.children[RIGHT], Some(&node.val), max)?;
+            let fits = left.abs_diff(right) <= 1 && node.height == 1 + left.max(right);
+            fits.then_some(node.height)
+        }
+        check(&self.root, None, None).is_some()
+    }
+
+    fn insert_at(link: &mut Link<T>, val: T) -> bool {
+        let node = match link {
+            Some(node) => node,
+            None => {
+                *link = Some(Box::new(AvlNode {
+                    val,
+                    height: 1,
+                    children: [None, None],
+                }));
+                return true;
+            }
+        };
+
+        let inserted = match val.cmp(&node.val) {
+            Ordering::Equal => return false,
+            Ordering::Less => Self::insert_at(&mut node.children[LEFT], val),
+            Ordering::Greater => Self::insert_at(&mut node.children[RIGHT], val),
+        };
+        if inserted {
+            Self::rebalance(link);
+        }
+        inserted
+    }
+
+    fn remove_at(link: &mut Link<T>, val: &T) -> bool {
+        let node = match link {
+            Some(node) => node,
+            None => return false,
+        };
+
+        let removed = match val.cmp(&node.val) {
+            Ordering::Less => Self::remove_at(&mut node.children[LEFT], val),
+            Ordering::Greater => Self::remove_at(&mut node.children[RIGHT], val),
+            Ordering::Equal => {
+                match (
+                    node.children[LEFT].is_some(),
+                    node.children[RIGHT].is_some(),
+                ) {
+                    // the successor takes place of the value
+                    (true, true) => node.val = Self::take_min(&mut node.children[RIGHT]),
+                    (true, false) => *link = node.children[LEFT].take(),
+                    (false, _) => *link = node.children[RIGHT].take(),
+                }
+                true
+            }
+        };
+        if removed {
+            Self::rebalance(link);
+        }
+        removed
+    }
+
+    // unlinks the minimum of the subtree and returns its value
+    fn take_min(link: &mut Link<T>) -> T {
+        let node = link.as_mut().unwrap();
+        if node.children[LEFT].is_some() {
+            let min = Self::take_min(&mut node.children[LEFT]);
+            Self::rebalance(link);
+            return min;
+        }
+
+        let node = link.take().unwrap();
+        let AvlNode { val, children, .. } = *node;
+        let [_, right] = children;
+        *link = right;
+        val
+    }
+
+    // restores heights and the balance after one value is added or removed below
+    //     c              b
+    //    /             /   \
+    //   a      ->     a     c
+    //    \
+    //     b
+    fn rebalance(link: &mut Link<T>) {
+        let node = match link {
+            Some(node) => node,
+            None => return,
+        };
+        node.update();
+
+        let balance = node.balance();
+        if balance > 1 {
+            // the left side is too high, its inner grandchild goes up twice
+            if node.children[LEFT].as_ref().unwrap().balance() < 0 {
+                Self::rotate(&mut node.children[LEFT], LEFT);
+            }
+            Self::rotate(link, RIGHT);
+        } else if balance < -1 {
+            if node.children[RIGHT].as_ref().unwrap().balance() > 0 {
+                Self::rotate(&mut node.children[RIGHT], RIGHT);
+            }
+            Self::rotate(link, LEFT);
+        }
+    }
+
+    // moves the node down to the dir side, its child from the other side
+    // takes its place
+    fn rotate(link: &mut Link<T>, dir: usize) {
+        let other = 1 - dir;
+        let mut node = link.take().unwrap();
+        let mut pivot = node.children[other].take().unwrap();
+
+        node.children[other] = pivot.children[dir].take();
+        node.update();
+        pivot.children[dir] = Some(node);
+        pivot.update();
+        *link = Some(pivot);
+    }
+}
+
+impl<T: Ord + Clone> OrderedSet<T> for AvlTree<T> {
+    fn insert(&mut self, val: T) -> bool {
+        let inserted = Self::insert_at(&mut self.root, val);
+        self.len += inserted as usize;
+        inserted
+    }
+
+    fn remove(&mut self, val: &T) -> bool {
+        let removed = Self::remove_at(&mut self.root, val);
+        self.len -= removed as usize;
+        removed
+    }
+
+    fn contains(&self, val: &T) -> bool {
+        let mut next = self.root.as_deref();
+        while let Some(node) = next {
+            next = match val.cmp(&node.val) {
+                Ordering::Equal => return true,
+                Ordering::Less => node.child(LEFT),
+                Ordering::Greater => node.child(RIGHT),
+            };
+        }
+        false
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = T> + '_> {
+        Box::new(AvlTree::iter(self).cloned())
+    }
+
+    fn range<R: RangeBounds<T>>(&self, range: R) -> Box<dyn Iterator<Item = T> + '_> {
+        Box::new(AvlTree::range(self, range).cloned())
+    }
+}
@@ -0,0 +1,69 @@
+use std::ops::RangeBounds;
+
+use super::OrderedSet;
+use crate::rb_tree::{after_start, before_end};
+
+// values in a sorted vector, the search is binary and fast thanks to the cache,
+// but insertion and removal shift everything after the place, O(n)
+#[derive(Debug, Default, Clone)]
+pub struct SortedVec<T> {
+    vals: Vec<T>,
+}
+
+impl<T: Ord + Clone> SortedVec<T> {
+    pub fn new() -> Self {
+        SortedVec { vals: Vec::new() }
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.vals
+    }
+
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> &[T] {
+        let from = self
+            .vals
+            .partition_point(|v| !after_start(v, range.start_bound()));
+        let to = self
+            .vals
+            .partition_point(|v| before_end(v, range.end_bound()));
+        &self.vals[from..to.max(from)]
+    }
+}
+
+impl<T: Ord + Clone> OrderedSet<T> for SortedVec<T> {
+    fn insert(&mut self, val: T) -> bool {
+        match self.vals.binary_search(&val) {
+            Ok(_) => false,
+            Err(i) => {
+                self.vals.insert(i, val);
+                true
+            }
+        }
+    }
+
+    fn remove(&mut self, val: &T) -> bool {
+        match self.vals.binary_search(val) {
+            Ok(i) => {
+                self.vals.remove(i);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    fn contains(&self, val: &T) -> bool {
+        self.vals.binary_search(val).is_ok()
+    }
+
+    fn len(&self) -> usize {
+        self.vals.len()
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = T> + '_> {
+        Box::new(self.vals.iter().cloned())
+    }
+
+    fn range<R: RangeBounds<T>>(&self, range: R) -> Box<dyn Iterator<Item = T> + '_> {
+        Box::new(SortedVec::range(self, range).iter().cloned())
+    }
+}
@@ -0,0 +1,94 @@
+#[cfg(test)]
+mod test {
+    use crate::{AvlTree, OrderedSet, RbTree, SortedVec, Treap};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use std::collections::BTreeSet;
+    use std::ops::Bound;
+
+    const STEPS: usize = 5000;
+    const MAX: i32 = 300;
+
+    // the same random operations on the set and on BTreeSet,
+    // every answer and the content in the end should be the same
+    fn differential<S: OrderedSet<i32>>(mut set: S, seed: u64, check: impl Fn(&S) -> bool) -> S {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut model = BTreeSet::new();
+
+        for step in 0..STEPS {
+            let v = rng.gen_range(0..MAX);
+            match rng.gen_range(0..4) {
+                0 | 1 => assert_eq!(set.insert(v), model.insert(v), "step {}", step),
+                2 => assert_eq!(set.remove(&v), model.remove(&v), "step {}", step),
+                _ => assert_eq!(set.contains(&v), model.contains(&v), "step {}", step),
+            }
+            assert_eq!(set.len(), model.len(), "step {}", step);
+            assert!(check(&set), "step {}", step);
+
+            if step % 100 == 0 {
+                let a = rng.gen_range(-10..MAX + 10);
+                let b = rng.gen_range(-10..MAX + 10);
+                let ranges = [
+                    (Bound::Included(a), Bound::Excluded(b)),
+                    (Bound::Excluded(a), Bound::Included(b)),
+                    (Bound::Unbounded, Bound::Included(b)),
+                    (Bound::Excluded(a), Bound::Unbounded),
+                ];
+                for r in ranges {
+                    let expected: Vec<i32> = OrderedSet::range(&model, r).collect();
+                    assert_eq!(set.range(r).collect::<Vec<i32>>(), expected, "{:?}", r);
+                }
+            }
+        }
+        assert!(set.iter().eq(model.iter().copied()));
+        set
+    }
+
+    #[test]
+    fn test_ordered_sets() {
+        for seed in 0..3 {
+            let t = differential(RbTree::new(), seed, |t| t.validate().is_ok());
+            assert!(t.height() as f64 <= t.stats().height_bound());
+
+            let t = differential(AvlTree::new(), seed, |t| t.is_valid());
+            // the AVL bound is about 1.44*log2(n+2)
+            assert!(t.height() as f64 <= 1.45 * ((t.len() + 2) as f64).log2());
+
+            let t = differential(Treap::with_seed(seed), seed, |t| t.is_valid());
+            assert!(t.height() < 4 * (t.len() + 1).ilog2() as usize + 4);
+
+            let v = differential(SortedVec::new(), seed, |_| true);
+            assert!(v.as_slice().windows(2).all(|w| w[0] < w[1]));
+
+            differential(BTreeSet::new(), seed, |_| true);
+        }
+    }
+
+    #[test]
+    fn test_sorted_input() {
+        // a sorted input is the worst case for a plain search tree
+        fn fill<S: OrderedSet<i32>>(mut set: S) -> S {
+            for v in 0..10_000 {
+                assert!(set.insert(v));
+            }
+            assert!(!set.insert(5));
+            for v in (0..10_000).step_by(2) {
+                assert!(set.remove(&v));
+            }
+            assert_eq!(set.len(), 5000);
+            assert_eq!(set.range(10..=15).collect::<Vec<i32>>(), vec![11, 13, 15]);
+            assert_eq!(
+                set.range((Bound::Included(15), Bound::Excluded(10)))
+                    .count(),
+                0
+            );
+            assert!(set.iter().eq((1..10_000).step_by(2)));
+            set
+        }
+
+        assert!(fill(AvlTree::new()).height() <= 14);
+        assert!(fill(Treap::with_seed(1)).height() < 50);
+        assert!(fill(RbTree::new()).height() <= 26);
+        assert!(fill(SortedVec::new()).len() == 5000);
+        assert!(fill(BTreeSet::new()).len() == 5000);
+    }
+}
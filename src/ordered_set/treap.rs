@@ -0,0 +1,206 @@
+use std::{cmp::Ordering, ops::RangeBounds};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{BoxNode, NodeIter, OrderedSet, LEFT, RIGHT};
+
+type Link<T> = Option<Box<TreapNode<T>>>;
+
+struct TreapNode<T> {
+    val: T,
+    // random, a parent has higher priority than its children
+    priority: u64,
+    children: [Link<T>; 2],
+}
+
+impl<T> BoxNode<T> for TreapNode<T> {
+    fn val(&self) -> &T {
+        &self.val
+    }
+
+    fn child(&self, pos: usize) -> Option<&Self> {
+        self.children[pos].as_deref()
+    }
+}
+
+fn priority_of<T>(link: &Link<T>) -> Option<u64> {
+    link.as_ref().map(|n| n.priority)
+}
+
+// a search tree by values and a heap by random priorities at the same time,
+// the shape is the same as if values were added in random order,
+// so the height is O(log n) on average whatever the real order is
+//
+// functions are recursive, the depth is O(log n) with high probability
+pub struct Treap<T> {
+    root: Link<T>,
+    len: usize,
+    rng: StdRng,
+}
+
+impl<T> Default for Treap<T> {
+    fn default() -> Self {
+        Treap {
+            root: None,
+            len: 0,
+            rng: StdRng::from_entropy(),
+        }
+    }
+}
+
+impl<T: Ord + Clone> Treap<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // the same seed and the same operations give the same shape
+    pub fn with_seed(seed: u64) -> Self {
+        Treap {
+            root: None,
+            len: 0,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        fn height<T>(link: &Link<T>) -> usize {
+            link.as_ref().map_or(0, |n| {
+                1 + height(&n.children[LEFT]).max(height(&n.children[RIGHT]))
+            })
+        }
+        height(&self.root)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        NodeIter::new(self.root.as_deref(), ..)
+    }
+
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> impl Iterator<Item = &T> + '_ {
+        NodeIter::new(self.root.as_deref(), range)
+    }
+
+    // checks the order of values and of priorities
+    pub fn is_valid(&self) -> bool {
+        fn check<T: Ord>(link: &Link<T>, min: Option<&T>, max: Option<&T>, top: u64) -> bool {
+            let node = match link {
+                Some(n) => n,
+                None => return true,
+            };
+            !(min.is_some_and(|m| node.val <= *m)
+                || max.is_some_and(|m| node.val >= *m)
+                || node.priority > top)
+                && check(&node.children[LEFT], min, Some(&node.val), node.priority)
+                && check(&node.children[RIGHT], Some(&node.val), max, node.priority)
+        }
+        check(&self.root, None, None, u64::MAX)
+    }
+
+    // the new node goes down as a leaf and is rotated up
+    // while its priority is higher than the parent one
+    fn insert_at(link: &mut Link<T>, val: T, priority: u64) -> bool {
+        let node = match link {
+            Some(node) => node,
+            None => {
+                *link = Some(Box::new(TreapNode {
+                    val,
+                    priority,
+                    children: [None, None],
+                }));
+                return true;
+            }
+        };
+
+        let pos = match val.cmp(&node.val) {
+            Ordering::Equal => return false,
+            Ordering::Less => LEFT,
+            Ordering::Greater => RIGHT,
+        };
+        if !Self::insert_at(&mut node.children[pos], val, priority) {
+            return false;
+        }
+        if priority_of(&node.children[pos]) > Some(node.priority) {
+            Self::rotate(link, 1 - pos);
+        }
+        true
+    }
+
+    // the node is rotated down under the child with higher priority
+    // until it has at most one child and could be unlinked
+    fn remove_at(link: &mut Link<T>, val: &T) -> bool {
+        let node = match link {
+            Some(node) => node,
+            None => return false,
+        };
+
+        match val.cmp(&node.val) {
+            Ordering::Less => Self::remove_at(&mut node.children[LEFT], val),
+            Ordering::Greater => Self::remove_at(&mut node.children[RIGHT], val),
+            Ordering::Equal => {
+                let [left, right] = &mut node.children;
+                match (priority_of(left), priority_of(right)) {
+                    (None, _) => *link = right.take(),
+                    (_, None) => *link = left.take(),
+                    (l, r) => {
+                        // the child with higher priority goes up
+                        let dir = if l > r { RIGHT } else { LEFT };
+                        Self::rotate(link, dir);
+                        let top = link.as_mut().unwrap();
+                        Self::remove_at(&mut top.children[dir], val);
+                    }
+                }
+                true
+            }
+        }
+    }
+
+    // moves the node down to the dir side, its child from the other side
+    // takes its place
+    fn rotate(link: &mut Link<T>, dir: usize) {
+        let other = 1 - dir;
+        let mut node = link.take().unwrap();
+        let mut pivot = node.children[other].take().unwrap();
+
+        node.children[other] = pivot.children[dir].take();
+        pivot.children[dir] = Some(node);
+        *link = Some(pivot);
+    }
+}
+
+impl<T: Ord + Clone> OrderedSet<T> for Treap<T> {
+    fn insert(&mut self, val: T) -> bool {
+        let priority = self.rng.gen();
+        let inserted = Self::insert_at(&mut self.root, val, priority);
+        self.len += inserted as usize;
+        inserted
+    }
+
+    fn remove(&mut self, val: &T) -> bool {
+        let removed = Self::remove_at(&mut self.root, val);
+        self.len -= removed as usize;
+        removed
+    }
+
+    fn contains(&self, val: &T) -> bool {
+        let mut next = self.root.as_deref();
+        while let Some(node) = next {
+            next = match val.cmp(&node.val) {
+                Ordering::Equal => return true,
+                Ordering::Less => node.child(LEFT),
+                Ordering::Greater => node.child(RIGHT),
+            };
+        }
+        false
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = T> + '_> {
+        Box::new(Treap::iter(self).cloned())
+    }
+
+    fn range<R: RangeBounds<T>>(&self, range: R) -> Box<dyn Iterator<Item = T> + '_> {
+        Box::new(Treap::range(self, range).cloned())
+    }
+}
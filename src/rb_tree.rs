@@ -1,15 +1,147 @@
 use std::{cell::RefCell, cmp::Ordering, mem, ptr, rc::Rc};
 
 mod node;
+#[cfg(test)]
+mod stress;
 mod tests;
-use node::*;
+pub use node::{Color, Node, RbTreeNode};
 mod ancestor;
 use ancestor::*;
+mod codec;
+pub use codec::{Codec, DecodeError, Encoding};
+mod dot;
+pub use dot::DotOptions;
+mod svg;
+mod tikz;
+pub use tikz::TikzOptions;
+mod printer;
+pub use printer::{Glyphs, PrintOptions, Printed};
+mod diagram;
+pub use diagram::{side_by_side, DiagramColors, DiagramOptions};
+mod validate;
+pub use validate::{Path, Side, TreeStats, Violation};
+mod observer;
+use observer::Listener;
+pub use observer::{insert_case, remove_case, Event, Observer, Operation, Rotation};
+mod counters;
+pub use counters::{CostCounters, Costs};
+mod playback;
+pub use playback::{Frame, Recording};
+mod top_down;
+pub use top_down::Strategy;
+mod llrb;
+pub use llrb::LlrbTree;
+mod iter;
+pub use iter::Iter;
+pub(crate) use iter::{after_start, before_end};
+mod tree234;
+pub use tree234::{Node234, Tree234};
+mod parse;
+pub use parse::ParseError;
+mod explain;
+mod recolor;
+pub use recolor::{Certificate, Coloring};
+mod enumerate;
+pub use enumerate::ValidTrees;
+mod search;
+pub use search::{worst_case, Found, Goal, SearchOptions, Step};
 
 #[derive(Debug)]
 pub struct RbTree<T> {
     pub root: Option<Node<T>>,
     len: usize,
+    observer: Option<Listener<T>>,
+    strategy: Strategy,
+    // reused by `add` and `remove` to avoid allocations, empty between calls
+    path: Ancestry<T>,
+}
+
+impl<T> Default for RbTree<T> {
+    fn default() -> Self {
+        RbTree {
+            root: None,
+            len: 0,
+            observer: None,
+            strategy: Strategy::BottomUp,
+            path: Vec::new(),
+        }
+    }
+}
+
+// copies every node, the observer stays with the original tree
+impl<T: Clone> Clone for RbTree<T> {
+    fn clone(&self) -> Self {
+        let mut tree = RbTree::default();
+        tree.root = self.root.as_ref().map(copy_tree);
+        tree.len = self.len;
+        tree.strategy = self.strategy;
+        tree
+    }
+}
+
+// the default drop goes down the tree recursively, a long enough chain of nodes
+// would overflow the stack
+impl<T> Drop for RbTree<T> {
+    fn drop(&mut self) {
+        dismantle(self.root.take());
+    }
+}
+
+// takes nodes apart one by one without recursion and extra memory,
+// left children are rotated up until the top node has no left child,
+// then it's dropped and its right child takes its place
+//     n        l
+//    / \      / \
+//   l   r ->  a   n
+//  / \           / \
+// a   b         b   r
+//
+// nodes held by somebody else are left untouched together with their subtrees
+fn dismantle<T>(root: Option<Node<T>>) {
+    let mut next = root.filter(|n| Rc::strong_count(n) == 1);
+
+    while let Some(node) = next {
+        let left = node.borrow_mut().children[Pos::LEFT].take();
+        next = match left.filter(|l| Rc::strong_count(l) == 1) {
+            Some(left) => {
+                let rest = left.borrow_mut().children[Pos::RIGHT].take();
+                node.borrow_mut().children[Pos::LEFT] = rest;
+                left.borrow_mut().children[Pos::RIGHT] = Some(node);
+                Some(left)
+            }
+            None => {
+                let right = node.borrow_mut().children[Pos::RIGHT].take();
+                right.filter(|r| Rc::strong_count(r) == 1)
+            }
+        };
+    }
+}
+
+fn copy_node<T: Clone>(node: &Node<T>) -> Node<T> {
+    let r = node.borrow();
+    Rc::new(RefCell::new(RbTreeNode {
+        val: r.val.clone(),
+        color: r.color,
+        children: [None, None],
+    }))
+}
+
+// uses explicit stack instead of recursion, any tree could be copied
+fn copy_tree<T: Clone>(root: &Node<T>) -> Node<T> {
+    let copy = copy_node(root);
+    // (original, its copy) whose children are not copied yet
+    let mut stack = vec![(root.clone(), copy.clone())];
+
+    while let Some((node, to)) = stack.pop() {
+        for pos in [Pos::LEFT, Pos::RIGHT] {
+            if let Some(child) = node.borrow().children[pos].as_ref() {
+                let child_copy = copy_node(child);
+                to.borrow_mut().children[pos] = Some(child_copy.clone());
+                stack.push((child.clone(), child_copy));
+            }
+        }
+    }
+    copy
 }
 
 impl<T> RbTree<T>
@@ -26,43 +158,139 @@ where
     // - numbers of BLACK levels in left and right subtries are the same
 
     pub fn new() -> Self {
-        RbTree { root: None, len: 0 }
+        Self::default()
     }
 
     pub fn len(&self) -> usize {
         self.len
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // removes all values, the observer stays
+    pub fn clear(&mut self) {
+        dismantle(self.root.take());
+        self.len = 0;
+    }
+
+    // visits values in ascending order, uses explicit stack instead of recursion
+    pub fn for_each<F: FnMut(&T)>(&self, mut f: F) {
+        let mut stack: Vec<Node<T>> = Vec::new();
+        let mut next = self.root.clone();
+
+        loop {
+            while let Some(node) = next {
+                next = node.borrow().children[Pos::LEFT].clone();
+                stack.push(node);
+            }
+            match stack.pop() {
+                Some(node) => {
+                    let r = node.borrow();
+                    f(&r.val);
+                    next = r.children[Pos::RIGHT].clone();
+                }
+                None => break,
+            }
+        }
+    }
+
+    // builds the tree from ascending values in O(n) without any rebalancing
+    //
+    // the middle value becomes the root, so depths of NIL nodes differ
+    // at most by one, all nodes are BLACK except the deepest level which is RED
+    //        4b
+    //       /  \
+    //     2b    6b
+    //    / \   /
+    //   1r 3r 5r
+    pub(crate) fn from_sorted(vals: Vec<T>) -> Self {
+        let len = vals.len();
+        if len == 0 {
+            return Self::new();
+        }
+        // depth of the deepest level, root has depth 0
+        let red_depth = len.ilog2() as usize;
+
+        let mut iter = vals.into_iter();
+        let mut tree = Self::new();
+        tree.root = Self::build_sorted(&mut iter, len, 0, red_depth);
+        tree.len = len;
+        tree
+    }
+
+    fn build_sorted(
+        iter: &mut std::vec::IntoIter<T>,
+        count: usize,
+        depth: usize,
+        red_depth: usize,
+    ) -> Option<Node<T>> {
+        if count == 0 {
+            return None;
+        }
+        // take values in order: left subtree, the node itself, right subtree
+        let left_count = count / 2;
+        let left = Self::build_sorted(iter, left_count, depth + 1, red_depth);
+
+        let color = if depth == red_depth && depth > 0 {
+            Color::Red
+        } else {
+            Color::Black
+        };
+        let node = Self::new_node(iter.next().unwrap(), color);
+
+        let right = Self::build_sorted(iter, count - left_count - 1, depth + 1, red_depth);
+        node.borrow_mut().children = [left, right];
+
+        Some(node)
+    }
+
     fn new_node(val: T, color: Color) -> Node<T> {
         Rc::new(RefCell::new(RbTreeNode {
-            val: val,
-            color: color,
+            val,
+            color,
             children: [None, None],
         }))
     }
 
     pub fn add(&mut self, val: T) {
+        self.emit(|| Event::Operation(Operation::Add));
+
         if self.root.is_none() {
             self.root = Some(Self::new_node(val, Color::Black));
             self.len += 1;
             return;
         }
 
-        let mut ancestors = Vec::new();
-        ancestors.push(Ancestor {
-            node: self.root.as_ref().unwrap().clone(),
-            position: Pos::LEFT,
-        });
+        match self.strategy {
+            Strategy::BottomUp => {
+                let mut ancestors = mem::take(&mut self.path);
+                ancestors.push(Ancestor {
+                    node: self.root.as_ref().unwrap().clone(),
+                    position: Pos::LEFT,
+                });
 
-        self.add_and_fix(val, &mut ancestors);
+                self.add_and_fix(val, &mut ancestors);
 
-        self.root.as_ref().unwrap().borrow_mut().color = Color::Black;
+                // keep the buffer but not the nodes
+                ancestors.clear();
+                self.path = ancestors;
+            }
+            Strategy::TopDown => self.add_top_down(val),
+        }
+
+        let root = self.root.clone().unwrap();
+        self.set_color(&root, Color::Black);
         self.len += 1;
     }
 
     fn add_and_fix(&mut self, val: T, ancestors: &mut Ancestry<T>) {
         // build hierarchy(ancestry)
         Self::find_leaf(&val, ancestors);
+        // every ancestor and the leaf once again to choose the side
+        let compared = ancestors.len() + 1;
+        self.emit(|| Event::Compare(compared));
 
         let leaf = ancestors.last().unwrap().node.clone();
         let new_one = Self::new_node(val, Color::Red);
@@ -89,182 +317,216 @@ where
     }
 
     fn find_leaf(val: &T, ancestors: &mut Ancestry<T>) {
-        let node = ancestors.last().unwrap().node.clone();
-        let r = node.borrow();
-
-        if *val <= r.val {
-            if let Some(child) = r.children[Pos::LEFT].as_ref() {
-                ancestors.push(Ancestor {
-                    node: child.clone(),
-                    position: Pos::LEFT,
-                });
-                return Self::find_leaf(val, ancestors);
+        loop {
+            let position;
+            let child;
+            {
+                let r = ancestors.last().unwrap().node.borrow();
+                position = if *val <= r.val { Pos::LEFT } else { Pos::RIGHT };
+                child = r.children[position].clone();
             }
-        } else {
-            // val > node.val
-            if let Some(child) = r.children[Pos::RIGHT].as_ref() {
-                ancestors.push(Ancestor {
-                    node: child.clone(),
-                    position: Pos::RIGHT,
-                });
-                return Self::find_leaf(val, ancestors);
+
+            match child {
+                Some(node) => ancestors.push(Ancestor { node, position }),
+                // if there is no child, we found a leaf
+                None => return,
             }
         }
-        // if there is no children, do nothing, we found a leaf
     }
 
     fn fix_insert(&mut self, ancestors: &mut Ancestry<T>) {
-        if ancestors.len() <= 2 {
-            return;
-        }
+        // case 1 moves the problem two levels up, the loop goes on from there
+        while ancestors.len() > 2 {
+            //    gparent
+            //    /    \
+            // uncle  parent
+            //        /    \
+            //   sibling   node
 
-        //    gparent
-        //    /    \
-        // uncle  parent
-        //        /    \
-        //   sibling   node
+            let node = ancestors.pop().unwrap();
+            let parent = ancestors.pop().unwrap();
+            let gparent = ancestors.last().unwrap();
 
-        let node = ancestors.pop().unwrap();
-        let parent = ancestors.pop().unwrap();
-        let gparent = ancestors.last().unwrap();
+            if parent.node.borrow().color == Color::Black {
+                // everything is already balanced
+                return;
+            }
 
-        if parent.node.borrow().color == Color::Black {
-            // everything is already balanced
-            return;
-        }
+            let uncle = gparent.node.borrow().children[Self::opposite_pos(parent.position)].clone();
 
-        let uncle = gparent.node.borrow().children[Self::opposite_pos(parent.position)].clone();
+            if let Some(uncle_node) = uncle {
+                if uncle_node.borrow().color == Color::Red {
+                    // case 1: uncle is RED, recolor and move up
+                    self.emit(|| Event::InsertCase(1));
 
-        if let Some(uncle_node) = uncle {
-            if uncle_node.borrow().color == Color::Red {
-                uncle_node.borrow_mut().color = Color::Black;
-                parent.node.borrow_mut().color = Color::Black;
-                gparent.node.borrow_mut().color = Color::Red;
+                    self.set_color(&uncle_node, Color::Black);
+                    self.set_color(&parent.node, Color::Black);
+                    self.set_color(&gparent.node, Color::Red);
 
-                self.fix_insert(ancestors);
-                return;
+                    continue;
+                }
             }
-        }
 
-        // uncle exists and has BLACK color
+            // uncle exists and has BLACK color
 
-        if parent.position == Pos::RIGHT {
-            if node.position == Pos::RIGHT {
-                // <left rotation>
-                // nodes are on the right side
-                // p and n are RED
-                // gp
-                //  \
-                //   p  ->   p
-                //    \     / \
-                //     n   gp  n
+            if parent.position == Pos::RIGHT {
+                if node.position == Pos::RIGHT {
+                    // case 2: <left rotation>
+                    // nodes are on the right side
+                    // p and n are RED
+                    // gp
+                    //  \
+                    //   p  ->   p
+                    //    \     / \
+                    //     n   gp  n
 
-                parent.node.borrow_mut().color = Color::Black;
-                gparent.node.borrow_mut().color = Color::Red;
+                    self.emit(|| Event::InsertCase(2));
+                    self.set_color(&parent.node, Color::Black);
+                    self.set_color(&gparent.node, Color::Red);
 
-                self.rotate_left(ancestors);
-            } else {
-                // <right left rotation>
-                // nodes on different sides
-                // p and n are RED
-                // gp      gp
-                //  \       \
-                //   p  ->   n  ->   n
-                //  /       / \     / \
-                // n      nil  p   gp  p
-
-                node.node.borrow_mut().color = Color::Black;
-                gparent.node.borrow_mut().color = Color::Red;
-
-                ancestors.push(parent);
-                self.rotate_right(ancestors);
-                ancestors.pop().unwrap();
-
-                self.rotate_left(ancestors);
-            }
-        } else {
-            if node.position == Pos::LEFT {
-                // <right rotation>
-                // nodes on the left side
-                // p and n are RED
-                //     gp
-                //    /
-                //   p  ->   p
-                //  /       / \
-                // n       n  gp
-
-                parent.node.borrow_mut().color = Color::Black;
-                gparent.node.borrow_mut().color = Color::Red;
-
-                self.rotate_right(ancestors);
+                    self.rotate_left(ancestors);
+                } else {
+                    // case 3: <right left rotation>
+                    // nodes on different sides
+                    // p and n are RED
+                    // gp      gp
+                    //  \       \
+                    //   p  ->   n  ->   n
+                    //  /       / \     / \
+                    // n      nil  p   gp  p
+
+                    self.emit(|| Event::InsertCase(3));
+                    self.set_color(&node.node, Color::Black);
+                    self.set_color(&gparent.node, Color::Red);
+
+                    ancestors.push(parent);
+                    self.rotate_right(ancestors);
+                    ancestors.pop().unwrap();
+
+                    self.rotate_left(ancestors);
+                }
             } else {
-                // <left right rotation>
-                // nodes on different sides
-                // p and n are RED
-                //   gp      gp
-                //  /       /
-                // p  ->   n  ->   n
-                //  \     / \     / \
-                //   n   p  nil  p  gp
-
-                node.node.borrow_mut().color = Color::Black;
-                gparent.node.borrow_mut().color = Color::Red;
-
-                ancestors.push(parent);
-                self.rotate_left(ancestors);
-                ancestors.pop().unwrap();
-
-                self.rotate_right(ancestors);
+                if node.position == Pos::LEFT {
+                    // case 2: <right rotation>
+                    // nodes on the left side
+                    // p and n are RED
+                    //     gp
+                    //    /
+                    //   p  ->   p
+                    //  /       / \
+                    // n       n  gp
+
+                    self.emit(|| Event::InsertCase(2));
+                    self.set_color(&parent.node, Color::Black);
+                    self.set_color(&gparent.node, Color::Red);
+
+                    self.rotate_right(ancestors);
+                } else {
+                    // case 3: <left right rotation>
+                    // nodes on different sides
+                    // p and n are RED
+                    //   gp      gp
+                    //  /       /
+                    // p  ->   n  ->   n
+                    //  \     / \     / \
+                    //   n   p  nil  p  gp
+
+                    self.emit(|| Event::InsertCase(3));
+                    self.set_color(&node.node, Color::Black);
+                    self.set_color(&gparent.node, Color::Red);
+
+                    ancestors.push(parent);
+                    self.rotate_left(ancestors);
+                    ancestors.pop().unwrap();
+
+                    self.rotate_right(ancestors);
+                }
             }
+            break;
         }
     }
 
     pub fn remove(&mut self, val: &T) -> bool {
+        self.emit(|| Event::Operation(Operation::Remove));
+
         if self.root.is_none() {
             return false;
         }
+        if self.strategy == Strategy::TopDown {
+            let found = self.remove_top_down(val);
+            if found {
+                self.len -= 1;
+            }
+            return found;
+        }
 
-        let mut ancestors = Vec::new();
+        let mut ancestors = mem::take(&mut self.path);
         ancestors.push(Ancestor {
             node: self.root.clone().unwrap(),
             position: Pos::LEFT,
         });
 
         let found = Self::find_node(val, &mut ancestors);
-        if !found {
-            return false;
+        let compared = ancestors.len();
+        self.emit(|| Event::Compare(compared));
+        if found {
+            self.remove_last(&mut ancestors);
+            self.len -= 1;
         }
 
-        self.remove_last(&mut ancestors);
-        self.len -= 1;
-        true
+        // keep the buffer but not the nodes
+        ancestors.clear();
+        self.path = ancestors;
+        found
     }
 
-    fn find_node(val: &T, ancestors: &mut Ancestry<T>) -> bool {
-        let node = ancestors.last().unwrap().node.clone();
-        let r = node.borrow();
+    pub fn contains(&self, val: &T) -> bool {
+        let mut next = self.root.clone();
 
-        match r.val.cmp(val) {
-            Ordering::Equal => true,
-            Ordering::Greater => {
-                if let Some(child) = r.children[Pos::LEFT].as_ref() {
-                    ancestors.push(Ancestor {
-                        node: child.clone(),
-                        position: Pos::LEFT,
-                    });
-                    return Self::find_node(val, ancestors);
-                }
-                false
+        while let Some(node) = next {
+            next = match node.borrow().val.cmp(val) {
+                Ordering::Equal => return true,
+                Ordering::Greater => node.borrow().children[Pos::LEFT].clone(),
+                Ordering::Less => node.borrow().children[Pos::RIGHT].clone(),
+            };
+        }
+        false
+    }
+
+    // nodes visited while searching for the value, the last one is the value
+    // itself if it's found
+    fn search_path(&self, val: &T) -> Vec<Node<T>> {
+        let mut path = Vec::new();
+        let mut next = self.root.clone();
+
+        while let Some(node) = next {
+            next = match node.borrow().val.cmp(val) {
+                Ordering::Equal => None,
+                Ordering::Greater => node.borrow().children[Pos::LEFT].clone(),
+                Ordering::Less => node.borrow().children[Pos::RIGHT].clone(),
+            };
+            path.push(node);
+        }
+        path
+    }
+
+    fn find_node(val: &T, ancestors: &mut Ancestry<T>) -> bool {
+        loop {
+            let position;
+            let child;
+            {
+                let r = ancestors.last().unwrap().node.borrow();
+                position = match r.val.cmp(val) {
+                    Ordering::Equal => return true,
+                    Ordering::Greater => Pos::LEFT,
+                    Ordering::Less => Pos::RIGHT,
+                };
+                child = r.children[position].clone();
             }
-            Ordering::Less => {
-                if let Some(child) = r.children[Pos::RIGHT].as_ref() {
-                    ancestors.push(Ancestor {
-                        node: child.clone(),
-                        position: Pos::RIGHT,
-                    });
-                    return Self::find_node(val, ancestors);
-                }
-                false
+
+            match child {
+                Some(node) => ancestors.push(Ancestor { node, position }),
+                None => return false,
             }
         }
     }
@@ -318,24 +580,20 @@ where
     }
 
     fn find_min_node(ancestors: &mut Ancestry<T>) {
-        debug_assert!(ancestors.len() >= 1);
+        debug_assert!(!ancestors.is_empty());
 
         // traverse to the left subtree
         // it gives to us the minimum successor
 
-        let node = ancestors.last().unwrap().node.clone();
-
-        if node.borrow().children[Pos::LEFT].is_some() {
-            // have left child
-            let pos = Pos::LEFT;
-            let next = node.borrow().children[pos].clone().unwrap();
-
-            ancestors.push(Ancestor {
-                node: next,
-                position: pos,
-            });
-
-            return Self::find_min_node(ancestors);
+        loop {
+            let next = ancestors.last().unwrap().node.borrow().children[Pos::LEFT].clone();
+            match next {
+                Some(node) => ancestors.push(Ancestor {
+                    node,
+                    position: Pos::LEFT,
+                }),
+                None => return,
+            }
         }
     }
 
@@ -359,7 +617,7 @@ where
 
         let a = &ancestors[a_i];
         let v1 = &ancestors[a_i + 1];
-        let vN = &ancestors[b_i - 1];
+        let v_n = &ancestors[b_i - 1];
         let b = &ancestors[b_i];
 
         // set 'x'
@@ -386,7 +644,7 @@ where
             b.node.borrow_mut().children[v1.position] = Some(a.node.clone());
         } else {
             b.node.borrow_mut().children[v1.position] = Some(v1.node.clone());
-            vN.node.borrow_mut().children[b.position] = Some(a.node.clone());
+            v_n.node.borrow_mut().children[b.position] = Some(a.node.clone());
         }
 
         // swap colors together with references
@@ -399,6 +657,11 @@ where
         unsafe {
             ptr::swap(&mut ancestors[a_i].node, &mut ancestors[b_i].node);
         }
+
+        self.emit_with(|copy| Event::SwapNodes {
+            a: copy(&ancestors[b_i].node.borrow().val),
+            b: copy(&ancestors[a_i].node.borrow().val),
+        });
     }
 
     // extracts node from the tree, pops last ancestor from ancestors
@@ -407,25 +670,29 @@ where
         let child_node = node.node.borrow_mut().children[child].take();
 
         // root is the target
-        if ancestors.len() == 0 {
-            self.root = match child_node {
-                Some(c) => {
-                    c.borrow_mut().color = Color::Black;
-                    Some(c)
-                }
-                None => None,
-            };
+        if ancestors.is_empty() {
+            self.root = child_node.clone();
+            self.emit_with(|copy| Event::Extract {
+                node: copy(&node.node.borrow().val),
+            });
+
+            if let Some(c) = child_node {
+                self.set_color(&c, Color::Black);
+            }
             return;
         }
 
         let parent = ancestors.last().unwrap();
         parent.node.borrow_mut().children[node.position] = child_node.clone();
+        self.emit_with(|copy| Event::Extract {
+            node: copy(&node.node.borrow().val),
+        });
 
         // keep red black properties
         if let Some(c) = child_node {
             if (node.node.borrow().color == Color::Red) || (c.borrow().color == Color::Red) {
                 // prevent two consecutive red nodes
-                c.borrow_mut().color = Color::Black;
+                self.set_color(&c, Color::Black);
             } else {
                 // keep number of black nodes in a path
                 self.fix_remove(ancestors, Self::opposite_pos(node.position));
@@ -438,210 +705,224 @@ where
         }
     }
 
-    fn fix_remove(&mut self, ancestors: &mut Ancestry<T>, sibling_position: usize) {
-        if ancestors.len() == 0 {
-            return;
-        }
-        //    gparent
-        //    /    \
-        // uncle  parent
-        //        /    \
-        //    sibling  node(extracted)
-        //    /    \
-        // nephew nephew
-
-        let sibling;
-        {
-            let parent = ancestors.last().unwrap();
-            sibling = parent.node.borrow().children[sibling_position].clone();
-        }
-        if let Some(sibling_node) = sibling {
-            if sibling_node.borrow().color == Color::Black {
-                let nephew_mask = Self::red_children(sibling_node.clone());
-
-                // both nephews are BLACK
-                if nephew_mask == 0b00 {
-                    sibling_node.borrow_mut().color = Color::Red;
-                    let parent = ancestors.pop().unwrap();
-
-                    if parent.node.borrow().color == Color::Black {
-                        // do that recursively
-                        self.fix_remove(ancestors, Self::opposite_pos(parent.position));
-                    } else {
-                        parent.node.borrow_mut().color = Color::Black;
-                    }
-                    return;
-                } else {
-                    // one or both nephews are RED
-
-                    let parent = ancestors.last().unwrap();
+    fn fix_remove(&mut self, ancestors: &mut Ancestry<T>, mut sibling_position: usize) {
+        // some cases move the problem up or keep fixing from the same node,
+        // the loop goes on from there
+        while !ancestors.is_empty() {
+            //    gparent
+            //    /    \
+            // uncle  parent
+            //        /    \
+            //    sibling  node(extracted)
+            //    /    \
+            // nephew nephew
 
-                    if sibling_position == Pos::LEFT {
-                        // both or left nephew is RED
-                        if nephew_mask == 0b11 || nephew_mask == 0b10 {
-                            // <right rotation>
-                            // nodes on the left side
-                            // s is BLACK, left nephew is RED
-                            //      p       s
-                            //     /       / \
-                            //    s  ->  nep  p
-                            //   / \         /
-                            // nep nep     nep
-                            //
-                            // s moved to p position, keep their colors
-                            // on the same place
-                            // set nep and p colors BLACK
-                            // to follow black heights rule
-
-                            sibling_node.borrow_mut().color = parent.node.borrow().color;
-                            {
-                                let nephew = sibling_node.borrow_mut().children[Pos::LEFT]
-                                    .clone()
-                                    .unwrap();
-                                nephew.borrow_mut().color = Color::Black;
-                            }
-                            parent.node.borrow_mut().color = Color::Black;
+            let sibling;
+            {
+                let parent = ancestors.last().unwrap();
+                sibling = parent.node.borrow().children[sibling_position].clone();
+            }
+            if let Some(sibling_node) = sibling {
+                if sibling_node.borrow().color == Color::Black {
+                    let nephew_mask = Self::red_children(sibling_node.clone());
 
-                            self.rotate_right(ancestors);
-                        } else {
-                            // <left right rotation>
-                            // nodes on different sides
-                            // s is BLACK, right nephew is RED
-                            //   p       p
-                            //  /       /
-                            // s  ->  nep  -> nep
-                            //  \     / \     / \
-                            //  nep  s   ?   s   p
-                            //
-                            // nep moved to p position, keep their colors
-                            // on the same place
-                            // set s color the same as p
-                            // to follow black heights rule
-                            {
-                                let nephew = sibling_node.borrow_mut().children[Pos::RIGHT]
-                                    .clone()
-                                    .unwrap();
-                                nephew.borrow_mut().color = parent.node.borrow().color;
-                            }
-                            parent.node.borrow_mut().color = Color::Black;
+                    if nephew_mask == 0b00 {
+                        // case 2: both nephews are BLACK
+                        self.emit(|| Event::RemoveCase(2));
 
-                            ancestors.push(Ancestor {
-                                node: sibling_node,
-                                position: Pos::LEFT,
-                            });
-                            self.rotate_left(ancestors);
-                            ancestors.pop();
+                        self.set_color(&sibling_node, Color::Red);
+                        let parent = ancestors.pop().unwrap();
 
-                            self.rotate_right(ancestors);
+                        if parent.node.borrow().color == Color::Black {
+                            // do that on the upper level
+                            sibling_position = Self::opposite_pos(parent.position);
+                            continue;
+                        } else {
+                            self.set_color(&parent.node, Color::Black);
                         }
                     } else {
-                        if nephew_mask == 0b11 || nephew_mask == 0b01 {
-                            // <left rotation>
-                            // nodes are on the right side
-                            // s is BLACK
-                            //  p          s
-                            //   \        / \
-                            //    s  ->  p  nep
-                            //   / \      \
-                            // nep nep    nep
-                            //
-                            // s moved to p position, keep their colors
-                            // on the same place
-                            // set nep and p colors BLACK
-                            // to follow black heights rule
-
-                            sibling_node.borrow_mut().color = parent.node.borrow().color;
-                            {
-                                let nephew = sibling_node.borrow_mut().children[Pos::RIGHT]
-                                    .clone()
-                                    .unwrap();
-                                nephew.borrow_mut().color = Color::Black;
+                        // one or both nephews are RED
+
+                        let parent = ancestors.last().unwrap();
+
+                        if sibling_position == Pos::LEFT {
+                            // both or left nephew is RED
+                            if nephew_mask == 0b11 || nephew_mask == 0b10 {
+                                // case 3: <right rotation>
+                                // nodes on the left side
+                                // s is BLACK, left nephew is RED
+                                //      p       s
+                                //     /       / \
+                                //    s  ->  nep  p
+                                //   / \         /
+                                // nep nep     nep
+                                //
+                                // s moved to p position, keep their colors
+                                // on the same place
+                                // set nep and p colors BLACK
+                                // to follow black heights rule
+
+                                self.emit(|| Event::RemoveCase(3));
+                                self.set_color(&sibling_node, parent.node.borrow().color);
+                                {
+                                    let nephew = sibling_node.borrow_mut().children[Pos::LEFT]
+                                        .clone()
+                                        .unwrap();
+                                    self.set_color(&nephew, Color::Black);
+                                }
+                                self.set_color(&parent.node, Color::Black);
+
+                                self.rotate_right(ancestors);
+                            } else {
+                                // case 4: <left right rotation>
+                                // nodes on different sides
+                                // s is BLACK, right nephew is RED
+                                //   p       p
+                                //  /       /
+                                // s  ->  nep  -> nep
+                                //  \     / \     / \
+                                //  nep  s   ?   s   p
+                                //
+                                // nep moved to p position, keep their colors
+                                // on the same place
+                                // set s color the same as p
+                                // to follow black heights rule
+
+                                self.emit(|| Event::RemoveCase(4));
+                                {
+                                    let nephew = sibling_node.borrow_mut().children[Pos::RIGHT]
+                                        .clone()
+                                        .unwrap();
+                                    self.set_color(&nephew, parent.node.borrow().color);
+                                }
+                                self.set_color(&parent.node, Color::Black);
+
+                                ancestors.push(Ancestor {
+                                    node: sibling_node,
+                                    position: Pos::LEFT,
+                                });
+                                self.rotate_left(ancestors);
+                                ancestors.pop();
+
+                                self.rotate_right(ancestors);
                             }
-                            parent.node.borrow_mut().color = Color::Black;
-
-                            self.rotate_left(ancestors);
                         } else {
-                            // <right left rotation>
-                            // nodes on different sides
-                            // s is BLACK
-                            //  p       p
-                            //   \       \
-                            //    s  ->  nep  -> nep
-                            //   /       / \     / \
-                            // nep      ?   s   p   s
-                            //
-                            // nep moved to p position, keep their colors
-                            // on the same place
-                            // set s color the same as p
-                            // to follow black heights rule
-                            {
-                                let nephew = sibling_node.borrow_mut().children[Pos::LEFT]
-                                    .clone()
-                                    .unwrap();
-                                nephew.borrow_mut().color = parent.node.borrow().color;
+                            if nephew_mask == 0b11 || nephew_mask == 0b01 {
+                                // case 3: <left rotation>
+                                // nodes are on the right side
+                                // s is BLACK
+                                //  p          s
+                                //   \        / \
+                                //    s  ->  p  nep
+                                //   / \      \
+                                // nep nep    nep
+                                //
+                                // s moved to p position, keep their colors
+                                // on the same place
+                                // set nep and p colors BLACK
+                                // to follow black heights rule
+
+                                self.emit(|| Event::RemoveCase(3));
+                                self.set_color(&sibling_node, parent.node.borrow().color);
+                                {
+                                    let nephew = sibling_node.borrow_mut().children[Pos::RIGHT]
+                                        .clone()
+                                        .unwrap();
+                                    self.set_color(&nephew, Color::Black);
+                                }
+                                self.set_color(&parent.node, Color::Black);
+
+                                self.rotate_left(ancestors);
+                            } else {
+                                // case 4: <right left rotation>
+                                // nodes on different sides
+                                // s is BLACK
+                                //  p       p
+                                //   \       \
+                                //    s  ->  nep  -> nep
+                                //   /       / \     / \
+                                // nep      ?   s   p   s
+                                //
+                                // nep moved to p position, keep their colors
+                                // on the same place
+                                // set s color the same as p
+                                // to follow black heights rule
+
+                                self.emit(|| Event::RemoveCase(4));
+                                {
+                                    let nephew = sibling_node.borrow_mut().children[Pos::LEFT]
+                                        .clone()
+                                        .unwrap();
+                                    self.set_color(&nephew, parent.node.borrow().color);
+                                }
+                                self.set_color(&parent.node, Color::Black);
+
+                                ancestors.push(Ancestor {
+                                    node: sibling_node,
+                                    position: Pos::RIGHT,
+                                });
+                                self.rotate_right(ancestors);
+                                ancestors.pop();
+
+                                self.rotate_left(ancestors);
                             }
-                            parent.node.borrow_mut().color = Color::Black;
-
-                            ancestors.push(Ancestor {
-                                node: sibling_node,
-                                position: Pos::RIGHT,
-                            });
-                            self.rotate_right(ancestors);
-                            ancestors.pop();
-
-                            self.rotate_left(ancestors);
                         }
                     }
-                }
-            } else {
-                let parent_node = ancestors.last().unwrap().node.clone();
-                parent_node.borrow_mut().color = Color::Red;
-                sibling_node.borrow_mut().color = Color::Black;
-
-                if sibling_position == Pos::LEFT {
-                    // <right rotation>
-                    // nodes on the left side
-                    // s is RED, so p and nep should be BLACK
-                    //      p           s
-                    //     / \         / \
-                    //    s   n  ->  nep  p
-                    //   / \         / \
-                    // nep nep     nep  n
-                    //
-                    // keep color fixing from new deleted node position
-
-                    self.rotate_right(ancestors);
                 } else {
-                    // <left rotation>
-                    // nodes are on the right side
-                    // s is RED, so p and nep should be BLACK
-                    //   p           s
-                    //  / \         / \
-                    // n   s  ->   p   nep
-                    //    / \     / \
-                    //  nep nep  n  nep
-                    //
-                    // keep color fixing from new deleted node position
+                    // case 1: sibling is RED
+                    self.emit(|| Event::RemoveCase(1));
 
-                    self.rotate_left(ancestors);
+                    let parent_node = ancestors.last().unwrap().node.clone();
+                    self.set_color(&parent_node, Color::Red);
+                    self.set_color(&sibling_node, Color::Black);
+
+                    if sibling_position == Pos::LEFT {
+                        // <right rotation>
+                        // nodes on the left side
+                        // s is RED, so p and nep should be BLACK
+                        //      p           s
+                        //     / \         / \
+                        //    s   n  ->  nep  p
+                        //   / \         / \
+                        // nep nep     nep  n
+                        //
+                        // keep color fixing from new deleted node position
+
+                        self.rotate_right(ancestors);
+                    } else {
+                        // <left rotation>
+                        // nodes are on the right side
+                        // s is RED, so p and nep should be BLACK
+                        //   p           s
+                        //  / \         / \
+                        // n   s  ->   p   nep
+                        //    / \     / \
+                        //  nep nep  n  nep
+                        //
+                        // keep color fixing from new deleted node position
+
+                        self.rotate_left(ancestors);
+                    }
+                    ancestors.push(Ancestor {
+                        node: parent_node,
+                        position: Self::opposite_pos(sibling_position),
+                    });
+                    continue;
                 }
-                ancestors.push(Ancestor {
-                    node: parent_node,
-                    position: Self::opposite_pos(sibling_position),
-                });
-                self.fix_remove(ancestors, sibling_position);
+            } else {
+                // if sibling is None, cannot balance on that level
+                // do balancing on upper level
+                //    gparent
+                //    /    \
+                // uncle  parent (next node)
+                //        /   \
+                //       nil child (node was deleted)
+
+                let parent = ancestors.pop().unwrap();
+                sibling_position = Self::opposite_pos(parent.position);
+                continue;
             }
-        } else {
-            // if sibling is None, cannot balance on that level
-            // do balancing on upper level
-            //    gparent
-            //    /    \
-            // uncle  parent (next node)
-            //        /   \
-            //       nil child (node was deleted)
-
-            let parent = ancestors.pop().unwrap();
-            self.fix_remove(ancestors, Self::opposite_pos(parent.position));
+            break;
         }
     }
 
@@ -677,12 +958,16 @@ where
         // exchange last ancestor from parent to pivot because of rotation
         parent.node = pivot.clone();
 
-        if ancestors.len() > 0 {
+        if !ancestors.is_empty() {
             let gparent = ancestors.last().unwrap();
             gparent.node.borrow_mut().children[parent.position] = Some(pivot);
         } else {
             self.root = Some(pivot);
         }
+        self.emit_with(|copy| Event::Rotate {
+            dir: Rotation::Left,
+            pivot: copy(&parent.node.borrow().val),
+        });
         ancestors.push(parent);
     }
 
@@ -700,17 +985,21 @@ where
         // exchange last ancestor from parent to pivot because of rotation
         parent.node = pivot.clone();
 
-        if ancestors.len() > 0 {
+        if !ancestors.is_empty() {
             let gparent = ancestors.last().unwrap();
             gparent.node.borrow_mut().children[parent.position] = Some(pivot);
         } else {
             self.root = Some(pivot);
         }
+        self.emit_with(|copy| Event::Rotate {
+            dir: Rotation::Right,
+            pivot: copy(&parent.node.borrow().val),
+        });
         ancestors.push(parent);
     }
 
     pub fn print(&self) {
-        Self::print_rec("".to_string(), self.root.clone(), true);
+        print!("{}", self);
 
         let v = if self.is_valid() {
             "valid"
@@ -719,112 +1008,4 @@ where
         };
         println!("RbTree is {}", v);
     }
-
-    fn print_rec(mut prefix: String, node: Option<Node<T>>, is_left: bool) {
-        if node.is_none() {
-            return;
-        }
-
-        print!("{}", prefix);
-
-        if is_left {
-            print!("└─");
-        } else {
-            print!("├─");
-        }
-
-        // print the value of the node
-        let node = node.unwrap();
-        let node_b = node.borrow();
-
-        let black = node_b.color == Color::Black;
-        println!("{:?}{}", node_b.val, if black { 'b' } else { 'r' });
-
-        // enter the next tree level - left and right branch
-        prefix += if is_left { "  " } else { "│ " };
-
-        Self::print_rec(prefix.clone(), node_b.children[Pos::RIGHT].clone(), false);
-        Self::print_rec(prefix, node_b.children[Pos::LEFT].clone(), true);
-    }
-
-    fn is_valid(&self) -> bool {
-        let root = self.root.clone();
-        if let Some(r) = root {
-            if r.borrow().color == Color::Red {
-                println!("The root should be BLACK");
-                return false;
-            }
-            let l_black = Self::black_height(r.borrow().children[Pos::LEFT].clone(), 1);
-            let r_black = Self::black_height(r.borrow().children[Pos::RIGHT].clone(), 1);
-
-            if l_black.is_err() {
-                println!("{}", l_black.unwrap_err());
-                return false;
-            }
-            if r_black.is_err() {
-                println!("{}", r_black.unwrap_err());
-                return false;
-            }
-        }
-        true
-    }
-
-    // returns black height of subtree if it's valid
-    fn black_height(node: Option<Node<T>>, mut level: u32) -> Result<u32, String> {
-        if let Some(n) = node {
-            let left = n.borrow().children[Pos::LEFT].clone();
-            let right = n.borrow().children[Pos::RIGHT].clone();
-
-            if left.is_some() && right.is_some() {
-                let left = left.unwrap();
-                let right = right.unwrap();
-
-                let add;
-                if n.borrow().color == Color::Red {
-                    if left.borrow_mut().color == Color::Red
-                        || right.borrow_mut().color == Color::Red
-                    {
-                        return Err(format!(
-                            "Two consecutive RED nodes, see val: {} on level: {}",
-                            n.borrow().val.to_string(),
-                            level.to_string()
-                        ));
-                    }
-                    add = 0;
-                } else {
-                    add = 1;
-                }
-
-                level += 1;
-                let l_black = Self::black_height(Some(left), level)?;
-                let r_black = Self::black_height(Some(right), level)?;
-
-                if l_black == r_black {
-                    return Ok(add + l_black);
-                } else {
-                    return Err(format!(
-                        "Different black heights, see val: {} on level {}, left: {} right: {}",
-                        n.borrow().val.to_string(),
-                        level.to_string(),
-                        l_black.to_string(),
-                        r_black.to_string()
-                    ));
-                }
-            } else {
-                let mut add = 0;
-
-                if let Some(l) = left {
-                    add += (l.borrow().color == Color::Black) as u32
-                }
-                if let Some(r) = right {
-                    add += (r.borrow().color == Color::Black) as u32
-                }
-                add += (n.borrow().color == Color::Black) as u32;
-
-                return Ok(add);
-            }
-        }
-        // nil node
-        Ok(0)
-    }
 }
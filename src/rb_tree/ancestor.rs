@@ -7,6 +7,7 @@ impl Pos {
     pub const RIGHT: usize = 1;
 }
 
+#[derive(Debug)]
 pub struct Ancestor<T> {
     pub node: Node<T>,
     pub position: usize,
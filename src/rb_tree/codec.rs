@@ -0,0 +1,336 @@
+use std::{fmt, fs, io, path::Path};
+
+use super::RbTree;
+
+// Binary format, all numbers are little endian:
+//
+// | magic "RBTR" | version u8 | flags u8 | width u8 | count u64 |
+// | entries ... | crc32 u32 |
+//
+// - flags bit 0 means entries are delta encoded
+// - width is the size of a fixed width entry, 0 for length prefixed entries
+// - plain entries are `width` bytes or varint length followed by bytes
+// - delta entries are varint of the first ordinal followed by
+//   varint differences between neighbours, values are sorted so
+//   the differences are never negative
+// - crc32 covers everything before it
+
+const MAGIC: &[u8; 4] = b"RBTR";
+const VERSION: u8 = 1;
+const FLAG_DELTA: u8 = 0b1;
+const HEADER_LEN: usize = 4 + 1 + 1 + 1 + 8;
+const CHECKSUM_LEN: usize = 4;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Encoding {
+    Plain,
+    // falls back to Plain if values don't support ordinals
+    Delta,
+}
+
+// values which can be stored in the binary format
+pub trait Codec: Sized {
+    // size of every encoded value in bytes, None for length prefixed values
+    const WIDTH: Option<usize>;
+
+    fn encode(&self, out: &mut Vec<u8>);
+    fn decode(bytes: &[u8]) -> Option<Self>;
+
+    // integer-like values have an order preserving mapping to u64
+    // which enables delta encoding
+    const ORDINAL: bool = false;
+
+    fn to_ordinal(&self) -> Option<u64> {
+        None
+    }
+    fn from_ordinal(_ordinal: u64) -> Option<Self> {
+        None
+    }
+}
+
+macro_rules! codec_unsigned {
+    ($($t:ty),*) => {$(
+        impl Codec for $t {
+            const WIDTH: Option<usize> = Some(std::mem::size_of::<$t>());
+            const ORDINAL: bool = true;
+
+            fn encode(&self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes());
+            }
+            fn decode(bytes: &[u8]) -> Option<Self> {
+                Some(<$t>::from_le_bytes(bytes.try_into().ok()?))
+            }
+            fn to_ordinal(&self) -> Option<u64> {
+                Some(*self as u64)
+            }
+            fn from_ordinal(ordinal: u64) -> Option<Self> {
+                <$t>::try_from(ordinal).ok()
+            }
+        }
+    )*};
+}
+
+macro_rules! codec_signed {
+    ($($t:ty),*) => {$(
+        impl Codec for $t {
+            const WIDTH: Option<usize> = Some(std::mem::size_of::<$t>());
+            const ORDINAL: bool = true;
+
+            fn encode(&self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes());
+            }
+            fn decode(bytes: &[u8]) -> Option<Self> {
+                Some(<$t>::from_le_bytes(bytes.try_into().ok()?))
+            }
+            // flipping the sign bit keeps negative values before positive ones
+            fn to_ordinal(&self) -> Option<u64> {
+                Some((*self as i64 as u64) ^ (1 << 63))
+            }
+            fn from_ordinal(ordinal: u64) -> Option<Self> {
+                <$t>::try_from((ordinal ^ (1 << 63)) as i64).ok()
+            }
+        }
+    )*};
+}
+
+codec_unsigned!(u8, u16, u32, u64);
+codec_signed!(i8, i16, i32, i64);
+
+impl Codec for String {
+    const WIDTH: Option<usize> = None;
+
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(self.as_bytes());
+    }
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+}
+
+#[derive(Debug)]
+pub enum DecodeError {
+    Io(io::Error),
+    BadMagic,
+    UnsupportedVersion(u8),
+    UnknownFlags(u8),
+    // the file stores entries of another width than the value type has
+    WidthMismatch { expected: u8, found: u8 },
+    // delta encoded file, but the value type has no ordinals
+    DeltaUnsupported,
+    Truncated,
+    TrailingBytes,
+    ChecksumMismatch { expected: u32, found: u32 },
+    InvalidValue { index: u64 },
+    Unsorted { index: u64 },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Io(e) => write!(f, "I/O error: {}", e),
+            DecodeError::BadMagic => write!(f, "not an RbTree file"),
+            DecodeError::UnsupportedVersion(v) => write!(f, "unsupported version {}", v),
+            DecodeError::UnknownFlags(flags) => write!(f, "unknown flags {:#010b}", flags),
+            DecodeError::WidthMismatch { expected, found } => {
+                write!(f, "entries are {} bytes wide, expected {}", found, expected)
+            }
+            DecodeError::DeltaUnsupported => {
+                write!(f, "delta encoding is not supported by the value type")
+            }
+            DecodeError::Truncated => write!(f, "unexpected end of data"),
+            DecodeError::TrailingBytes => write!(f, "unexpected bytes after the last entry"),
+            DecodeError::ChecksumMismatch { expected, found } => write!(
+                f,
+                "checksum mismatch, expected {:#010x} found {:#010x}",
+                expected, found
+            ),
+            DecodeError::InvalidValue { index } => write!(f, "invalid value at entry {}", index),
+            DecodeError::Unsorted { index } => {
+                write!(f, "entry {} is less than the previous one", index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl From<io::Error> for DecodeError {
+    fn from(e: io::Error) -> Self {
+        DecodeError::Io(e)
+    }
+}
+
+impl<T> RbTree<T>
+where
+    T: std::fmt::Debug + std::cmp::Ord + std::cmp::Eq + std::fmt::Display + Codec,
+{
+    pub fn to_bytes(&self, encoding: Encoding) -> Vec<u8> {
+        let delta = encoding == Encoding::Delta && T::ORDINAL;
+
+        let mut out = Vec::with_capacity(HEADER_LEN + self.len * T::WIDTH.unwrap_or(1));
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        out.push(if delta { FLAG_DELTA } else { 0 });
+        out.push(T::WIDTH.unwrap_or(0) as u8);
+        out.extend_from_slice(&(self.len as u64).to_le_bytes());
+
+        let mut prev = 0;
+        let mut entry = Vec::new();
+        self.for_each(|val| {
+            if delta {
+                let ordinal = val.to_ordinal().unwrap();
+                // values are visited in ascending order
+                write_varint(&mut out, ordinal - prev);
+                prev = ordinal;
+            } else if T::WIDTH.is_some() {
+                val.encode(&mut out);
+            } else {
+                entry.clear();
+                val.encode(&mut entry);
+                write_varint(&mut out, entry.len() as u64);
+                out.extend_from_slice(&entry);
+            }
+        });
+
+        let checksum = crc32(&out);
+        out.extend_from_slice(&checksum.to_le_bytes());
+        out
+    }
+
+    pub fn write_to<W: io::Write>(&self, mut writer: W, encoding: Encoding) -> io::Result<()> {
+        writer.write_all(&self.to_bytes(encoding))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P, encoding: Encoding) -> io::Result<()> {
+        fs::write(path, self.to_bytes(encoding))
+    }
+
+    // rebuilds the tree in O(n), rejects corrupted or unsorted data
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        if bytes.len() < MAGIC.len() || &bytes[..MAGIC.len()] != MAGIC {
+            return Err(DecodeError::BadMagic);
+        }
+        if bytes.len() < HEADER_LEN + CHECKSUM_LEN {
+            return Err(DecodeError::Truncated);
+        }
+
+        let (data, tail) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+        let expected = u32::from_le_bytes(tail.try_into().unwrap());
+        let found = crc32(data);
+        if expected != found {
+            return Err(DecodeError::ChecksumMismatch { expected, found });
+        }
+
+        let version = data[4];
+        if version != VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+        let flags = data[5];
+        if flags & !FLAG_DELTA != 0 {
+            return Err(DecodeError::UnknownFlags(flags));
+        }
+        let delta = flags & FLAG_DELTA != 0;
+        if delta && !T::ORDINAL {
+            return Err(DecodeError::DeltaUnsupported);
+        }
+
+        let width = data[6];
+        let expected_width = T::WIDTH.unwrap_or(0) as u8;
+        if width != expected_width {
+            return Err(DecodeError::WidthMismatch {
+                expected: expected_width,
+                found: width,
+            });
+        }
+        let count = u64::from_le_bytes(data[7..HEADER_LEN].try_into().unwrap());
+
+        let mut input = &data[HEADER_LEN..];
+        // every entry takes at least one byte, don't trust count blindly
+        let mut vals: Vec<T> = Vec::with_capacity(count.min(input.len() as u64) as usize);
+        let mut ordinal = 0u64;
+
+        for index in 0..count {
+            let val = if delta {
+                let diff = read_varint(&mut input, index)?;
+                ordinal = ordinal
+                    .checked_add(diff)
+                    .ok_or(DecodeError::InvalidValue { index })?;
+                T::from_ordinal(ordinal).ok_or(DecodeError::InvalidValue { index })?
+            } else {
+                let len = match T::WIDTH {
+                    Some(w) => w,
+                    None => read_varint(&mut input, index)? as usize,
+                };
+                if input.len() < len {
+                    return Err(DecodeError::Truncated);
+                }
+                let (entry, rest) = input.split_at(len);
+                input = rest;
+                T::decode(entry).ok_or(DecodeError::InvalidValue { index })?
+            };
+
+            if let Some(last) = vals.last() {
+                if val < *last {
+                    return Err(DecodeError::Unsorted { index });
+                }
+            }
+            vals.push(val);
+        }
+
+        if !input.is_empty() {
+            return Err(DecodeError::TrailingBytes);
+        }
+        Ok(Self::from_sorted(vals))
+    }
+
+    pub fn read_from<R: io::Read>(mut reader: R) -> Result<Self, DecodeError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, DecodeError> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+}
+
+// LEB128, 7 bits per byte, the high bit means more bytes follow
+fn write_varint(out: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        out.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+// a number which doesn't fit into u64 makes the entry at `index` invalid
+fn read_varint(input: &mut &[u8], index: u64) -> Result<u64, DecodeError> {
+    let mut v = 0u64;
+    let mut shift = 0;
+    loop {
+        let (&byte, rest) = input.split_first().ok_or(DecodeError::Truncated)?;
+        *input = rest;
+
+        if shift == 63 && byte > 1 || shift > 63 {
+            return Err(DecodeError::InvalidValue { index });
+        }
+        v |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(v);
+        }
+        shift += 7;
+    }
+}
+
+// CRC-32/ISO-HDLC, the same one zip and png use
+pub(super) fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
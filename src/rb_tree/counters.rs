@@ -0,0 +1,197 @@
+use std::{cell::RefCell, fmt, rc::Rc};
+
+use super::{node::*, Event, Observer, Operation, Rotation};
+
+// work done by one or many operations
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct Costs {
+    pub comparisons: usize,
+    // left and right together, kept separately to get the maximum of one operation
+    pub rotations: usize,
+    pub left_rotations: usize,
+    pub right_rotations: usize,
+    pub recolors: usize,
+    pub swaps: usize,
+}
+
+impl Costs {
+    fn add(&mut self, other: &Costs) {
+        self.comparisons += other.comparisons;
+        self.rotations += other.rotations;
+        self.left_rotations += other.left_rotations;
+        self.right_rotations += other.right_rotations;
+        self.recolors += other.recolors;
+        self.swaps += other.swaps;
+    }
+
+    fn max(&mut self, other: &Costs) {
+        self.comparisons = self.comparisons.max(other.comparisons);
+        self.rotations = self.rotations.max(other.rotations);
+        self.left_rotations = self.left_rotations.max(other.left_rotations);
+        self.right_rotations = self.right_rotations.max(other.right_rotations);
+        self.recolors = self.recolors.max(other.recolors);
+        self.swaps = self.swaps.max(other.swaps);
+    }
+}
+
+// counts work done by `add` and `remove`, share it to read the numbers later
+// let counters = Rc::new(RefCell::new(CostCounters::default()));
+// tree.set_observer(Box::new(counters.clone()));
+#[derive(Debug, Default, Clone)]
+pub struct CostCounters {
+    pub adds: usize,
+    pub removes: usize,
+    // the last operation, it's updated while the operation goes on
+    pub last: Costs,
+    pub total_add: Costs,
+    pub total_remove: Costs,
+    // the worst operation for every counter separately
+    pub max_add: Costs,
+    pub max_remove: Costs,
+    // how many times every case ran, the case 1 is at 0
+    pub insert_cases: [usize; 3],
+    pub remove_cases: [usize; 4],
+    current: Option<Operation>,
+}
+
+impl CostCounters {
+    pub fn total(&self) -> Costs {
+        let mut total = self.total_add;
+        total.add(&self.total_remove);
+        total
+    }
+
+    // cases which never ran, e.g. (Operation::Remove, 4)
+    pub fn uncovered(&self) -> Vec<(Operation, u8)> {
+        let never = |op: Operation, cases: &[usize]| {
+            let cases: Vec<(Operation, u8)> = (1..=cases.len() as u8)
+                .filter(|&n| cases[n as usize - 1] == 0)
+                .map(|n| (op, n))
+                .collect();
+            cases
+        };
+        let mut uncovered = never(Operation::Add, &self.insert_cases);
+        uncovered.extend(never(Operation::Remove, &self.remove_cases));
+        uncovered
+    }
+
+    pub fn on_event<T>(&mut self, event: &Event<T>) {
+        match event {
+            Event::Operation(op) => {
+                self.finish();
+                self.current = Some(*op);
+                self.last = Costs::default();
+                match op {
+                    Operation::Add => self.adds += 1,
+                    Operation::Remove => self.removes += 1,
+                }
+            }
+            Event::Compare(n) => self.last.comparisons += n,
+            Event::Rotate {
+                dir: Rotation::Left,
+                ..
+            } => {
+                self.last.rotations += 1;
+                self.last.left_rotations += 1;
+            }
+            Event::Rotate {
+                dir: Rotation::Right,
+                ..
+            } => {
+                self.last.rotations += 1;
+                self.last.right_rotations += 1;
+            }
+            Event::Recolor { .. } => self.last.recolors += 1,
+            Event::SwapNodes { .. } => self.last.swaps += 1,
+            Event::InsertCase(n) => count_case(&mut self.insert_cases, *n),
+            Event::RemoveCase(n) => count_case(&mut self.remove_cases, *n),
+            Event::Extract { .. } => {}
+        }
+    }
+
+    // the operation in progress is added to totals only when the next one starts,
+    // so totals and maximums are always up to date without an explicit end event
+    fn finish(&mut self) {
+        match self.current.take() {
+            Some(Operation::Add) => {
+                self.total_add.add(&self.last);
+                self.max_add.max(&self.last);
+            }
+            Some(Operation::Remove) => {
+                self.total_remove.add(&self.last);
+                self.max_remove.max(&self.last);
+            }
+            None => {}
+        }
+    }
+
+    // the same numbers after the last operation is finished
+    pub fn snapshot(&self) -> CostCounters {
+        let mut copy = self.clone();
+        copy.finish();
+        copy
+    }
+}
+
+// cases are numbered from 1, events are public, so unknown numbers are ignored
+fn count_case(cases: &mut [usize], n: u8) {
+    if let Some(count) = (n as usize).checked_sub(1).and_then(|i| cases.get_mut(i)) {
+        *count += 1;
+    }
+}
+
+impl<T> Observer<T> for Rc<RefCell<CostCounters>> {
+    fn on_event(&mut self, event: &Event<T>, _root: Option<&Node<T>>) {
+        self.borrow_mut().on_event(event);
+    }
+}
+
+//            count  compare  rotate  recolor  swap
+// add           10     2.40    0.60     1.20  0.00
+// add max               4       2        3     0
+impl fmt::Display for CostCounters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let c = self.snapshot();
+        writeln!(
+            f,
+            "{:<10} {:>6} {:>8} {:>8} {:>8} {:>6}",
+            "", "count", "compare", "rotate", "recolor", "swap"
+        )?;
+        for (name, count, total, max) in [
+            ("add", c.adds, &c.total_add, &c.max_add),
+            ("remove", c.removes, &c.total_remove, &c.max_remove),
+        ] {
+            let avg = |n: usize| n as f64 / count.max(1) as f64;
+            writeln!(
+                f,
+                "{:<10} {:>6} {:>8.2} {:>8.2} {:>8.2} {:>6.2}",
+                name,
+                count,
+                avg(total.comparisons),
+                avg(total.rotations),
+                avg(total.recolors),
+                avg(total.swaps)
+            )?;
+            writeln!(
+                f,
+                "{:<10} {:>6} {:>8} {:>8} {:>8} {:>6}",
+                format!("{} max", name),
+                "",
+                max.comparisons,
+                max.rotations,
+                max.recolors,
+                max.swaps
+            )?;
+        }
+
+        write!(f, "insert cases:")?;
+        for (i, n) in c.insert_cases.iter().enumerate() {
+            write!(f, " {}: {}", i + 1, n)?;
+        }
+        write!(f, "\nremove cases:")?;
+        for (i, n) in c.remove_cases.iter().enumerate() {
+            write!(f, " {}: {}", i + 1, n)?;
+        }
+        writeln!(f)
+    }
+}
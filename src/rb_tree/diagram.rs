@@ -0,0 +1,217 @@
+use super::{ancestor::Pos, node::*, RbTree};
+
+const ANSI_RED: &str = "\x1b[31m";
+const ANSI_RESET: &str = "\x1b[0m";
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum DiagramColors {
+    // BLACK nodes are [5], RED nodes are (5)
+    Brackets,
+    // RED nodes are painted, BLACK nodes are left as is
+    Ansi,
+}
+
+pub struct DiagramOptions<'a, T> {
+    pub colors: DiagramColors,
+    // used instead of `{:?}` to format values
+    pub format: Option<&'a dyn Fn(&T) -> String>,
+}
+
+impl<'a, T> Default for DiagramOptions<'a, T> {
+    fn default() -> Self {
+        DiagramOptions {
+            colors: DiagramColors::Brackets,
+            format: None,
+        }
+    }
+}
+
+// rendered subtree, every line has exactly `width` visible chars
+struct Block {
+    lines: Vec<String>,
+    width: usize,
+    // position of the subtree root in the first line
+    middle: usize,
+}
+
+impl<T> RbTree<T>
+where
+    T: std::fmt::Debug + std::cmp::Ord + std::cmp::Eq + std::fmt::Display,
+{
+    // draws the tree top-down the way textbooks do
+    //      _[5]_
+    //     /     \
+    //   _[3]   [8]
+    //  /
+    // (1)
+    // every node takes its own columns, so the size grows with the square
+    // of the height on degenerate trees
+    pub fn diagram(&self) -> String {
+        self.diagram_with(&DiagramOptions::default())
+    }
+
+    pub fn diagram_with(&self, options: &DiagramOptions<T>) -> String {
+        let mut out = String::new();
+        if let Some(root) = self.root.as_ref() {
+            for line in Self::block(root, options).lines {
+                out += line.trim_end();
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    // builds the blocks bottom-up with explicit stack instead of recursion,
+    // the left child is entered first so its block is under the right one
+    fn block(root: &Node<T>, options: &DiagramOptions<T>) -> Block {
+        // (node, whether the children are done)
+        let mut stack = vec![(root.clone(), false)];
+        let mut blocks = Vec::new();
+
+        while let Some((node, exit)) = stack.pop() {
+            let r = node.borrow();
+            if !exit {
+                stack.push((node.clone(), true));
+                for pos in [Pos::RIGHT, Pos::LEFT] {
+                    if let Some(child) = r.children[pos].as_ref() {
+                        stack.push((child.clone(), false));
+                    }
+                }
+                continue;
+            }
+
+            let right = r.children[Pos::RIGHT].as_ref().and_then(|_| blocks.pop());
+            let left = r.children[Pos::LEFT].as_ref().and_then(|_| blocks.pop());
+            let (label, u) = Self::label(&r, options);
+            blocks.push(join(label, u, left, right));
+        }
+        blocks.pop().unwrap()
+    }
+
+    // returns the label with its visible width
+    fn label(r: &RbTreeNode<T>, options: &DiagramOptions<T>) -> (String, usize) {
+        let mut val = match options.format {
+            Some(format) => format(&r.val),
+            None => format!("{:?}", r.val),
+        };
+        // without brackets an empty label still takes a column, edges hang below it
+        if val.is_empty() && options.colors == DiagramColors::Ansi {
+            val.push(' ');
+        }
+        let (label, u) = match (options.colors, r.color) {
+            (DiagramColors::Brackets, Color::Black) => (format!("[{}]", val), 2),
+            (DiagramColors::Brackets, Color::Red) => (format!("({})", val), 2),
+            (DiagramColors::Ansi, Color::Red) => (format!("{}{}{}", ANSI_RED, val, ANSI_RESET), 0),
+            (DiagramColors::Ansi, Color::Black) => (val.clone(), 0),
+        };
+        // visible width of the label
+        (label, u + val.chars().count())
+    }
+}
+
+// puts the label above the blocks of its children
+fn join(label: String, u: usize, left: Option<Block>, right: Option<Block>) -> Block {
+    match (left, right) {
+        (None, None) => Block {
+            lines: vec![label],
+            width: u,
+            middle: u / 2,
+        },
+        (Some(left), None) => {
+            let (n, p) = (left.width, left.middle);
+            let first = format!("{}{}{}", spaces(p + 1), "_".repeat(n - p - 1), label);
+            let second = format!("{}/{}", spaces(p), spaces(n - p - 1 + u));
+
+            let mut lines = vec![first, second];
+            lines.extend(left.lines.into_iter().map(|l| l + &spaces(u)));
+            Block {
+                lines,
+                width: n + u,
+                middle: n + u / 2,
+            }
+        }
+        (None, Some(right)) => {
+            let (m, q) = (right.width, right.middle);
+            let first = format!("{}{}{}", label, "_".repeat(q), spaces(m - q));
+            let second = format!("{}\\{}", spaces(u + q), spaces(m - q - 1));
+
+            let mut lines = vec![first, second];
+            lines.extend(right.lines.into_iter().map(|l| spaces(u) + &l));
+            Block {
+                lines,
+                width: m + u,
+                middle: u / 2,
+            }
+        }
+        (Some(left), Some(right)) => {
+            let (n, p) = (left.width, left.middle);
+            let (m, q) = (right.width, right.middle);
+            let first = format!(
+                "{}{}{}{}{}",
+                spaces(p + 1),
+                "_".repeat(n - p - 1),
+                label,
+                "_".repeat(q),
+                spaces(m - q)
+            );
+            let second = format!(
+                "{}/{}\\{}",
+                spaces(p),
+                spaces(n - p - 1 + u + q),
+                spaces(m - q - 1)
+            );
+
+            let mut lines = vec![first, second];
+            let height = left.lines.len().max(right.lines.len());
+            let mut l_lines = left.lines.into_iter();
+            let mut r_lines = right.lines.into_iter();
+            for _ in 0..height {
+                let l = l_lines.next().unwrap_or_else(|| spaces(n));
+                let r = r_lines.next().unwrap_or_else(|| spaces(m));
+                lines.push(l + &spaces(u) + &r);
+            }
+            Block {
+                lines,
+                width: n + m + u,
+                middle: n + u / 2,
+            }
+        }
+    }
+}
+
+// puts two diagrams next to each other, handy to compare a tree before and after
+pub fn side_by_side(left: &str, right: &str, gap: usize) -> String {
+    let l_lines: Vec<&str> = left.lines().collect();
+    let r_lines: Vec<&str> = right.lines().collect();
+    let width = l_lines.iter().map(|l| visible_len(l)).max().unwrap_or(0);
+
+    let mut out = String::new();
+    for i in 0..l_lines.len().max(r_lines.len()) {
+        let l = l_lines.get(i).copied().unwrap_or("");
+        let r = r_lines.get(i).copied().unwrap_or("");
+
+        let row = format!("{}{}{}", l, spaces(width - visible_len(l) + gap), r);
+        out += row.trim_end();
+        out.push('\n');
+    }
+    out
+}
+
+fn spaces(n: usize) -> String {
+    " ".repeat(n)
+}
+
+// length without ANSI escape sequences
+fn visible_len(s: &str) -> usize {
+    let mut len = 0;
+    let mut escape = false;
+    for c in s.chars() {
+        match (escape, c) {
+            (false, '\x1b') => escape = true,
+            (false, _) => len += 1,
+            (true, 'm') => escape = false,
+            (true, _) => {}
+        }
+    }
+    len
+}
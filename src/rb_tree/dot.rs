@@ -0,0 +1,234 @@
+use std::{cell::RefCell, collections::HashSet, fmt::Write as _, io, rc::Rc};
+
+use super::{ancestor::Pos, node::*, RbTree};
+
+const HIGHLIGHT: &str = "#ffb000";
+
+#[derive(Debug)]
+pub struct DotOptions<'a, T> {
+    // draw NIL leaves as small black boxes
+    pub nil_leaves: bool,
+    // highlight nodes and edges visited while searching for the value
+    pub search_path: Option<&'a T>,
+    // highlight the subtree rooted at the node with the value
+    pub subtree: Option<&'a T>,
+}
+
+impl<'a, T> Default for DotOptions<'a, T> {
+    fn default() -> Self {
+        DotOptions {
+            nil_leaves: false,
+            search_path: None,
+            subtree: None,
+        }
+    }
+}
+
+impl<T> RbTree<T>
+where
+    T: std::fmt::Debug + std::cmp::Ord + std::cmp::Eq + std::fmt::Display,
+{
+    // renders the tree in Graphviz DOT language, use `dot -Tpng` to get an image
+    pub fn to_dot(&self) -> String {
+        self.to_dot_with(&DotOptions::default())
+    }
+
+    pub fn to_dot_with(&self, options: &DotOptions<T>) -> String {
+        let mut path = HashSet::new();
+        if let Some(val) = options.search_path {
+            for node in self.search_path(val) {
+                path.insert(Rc::as_ptr(&node));
+            }
+        }
+        let subtree = options
+            .subtree
+            .and_then(|val| self.search_path(val).pop())
+            .filter(|node| Some(&node.borrow().val) == options.subtree)
+            .map(|node| Rc::as_ptr(&node));
+
+        let mut dot = Dot {
+            out: String::new(),
+            options,
+            path,
+            subtree,
+            ids: 0,
+        };
+
+        dot.out += "digraph RbTree {\n";
+        dot.out += "    graph [ordering=out];\n";
+        dot.out +=
+            "    node [shape=circle, style=filled, fontcolor=white, fontname=\"Helvetica\"];\n";
+        dot.out += "    edge [arrowhead=none];\n";
+        if let Some(root) = self.root.as_ref() {
+            dot.tree(root);
+        }
+        dot.out += "}\n";
+        dot.out
+    }
+
+    pub fn write_dot<W: io::Write>(&self, writer: W) -> io::Result<()> {
+        self.write_dot_with(writer, &DotOptions::default())
+    }
+
+    pub fn write_dot_with<W: io::Write>(
+        &self,
+        mut writer: W,
+        options: &DotOptions<T>,
+    ) -> io::Result<()> {
+        writer.write_all(self.to_dot_with(options).as_bytes())
+    }
+}
+
+struct Dot<'a, 'o, T> {
+    out: String,
+    options: &'a DotOptions<'o, T>,
+    // nodes on the search path
+    path: HashSet<*const RefCell<RbTreeNode<T>>>,
+    // root of the highlighted subtree
+    subtree: Option<*const RefCell<RbTreeNode<T>>>,
+    ids: usize,
+}
+
+enum Step<T> {
+    Node {
+        node: Node<T>,
+        in_subtree: bool,
+        // id of the parent and whether the edge to it is highlighted
+        parent: Option<(String, bool)>,
+    },
+    // child slot of a written node
+    Slot {
+        node: Node<T>,
+        pos: usize,
+        id: String,
+        in_subtree: bool,
+        on_path: bool,
+    },
+    // edge written after the subtree below it
+    Edge {
+        from: String,
+        to: String,
+        highlight: bool,
+    },
+}
+
+impl<'a, 'o, T: std::fmt::Display> Dot<'a, 'o, T> {
+    // uses explicit stack instead of recursion, the left slot is pushed last
+    // so its subtree is written first
+    fn tree(&mut self, root: &Node<T>) {
+        let mut stack = vec![Step::Node {
+            node: root.clone(),
+            in_subtree: false,
+            parent: None,
+        }];
+
+        while let Some(step) = stack.pop() {
+            match step {
+                Step::Node {
+                    node,
+                    in_subtree,
+                    parent,
+                } => {
+                    let (id, in_subtree, on_path) = self.node(&node, in_subtree);
+                    if let Some((from, highlight)) = parent {
+                        stack.push(Step::Edge {
+                            from,
+                            to: id.clone(),
+                            highlight,
+                        });
+                    }
+                    for pos in [Pos::RIGHT, Pos::LEFT] {
+                        stack.push(Step::Slot {
+                            node: node.clone(),
+                            pos,
+                            id: id.clone(),
+                            in_subtree,
+                            on_path,
+                        });
+                    }
+                }
+                Step::Slot {
+                    node,
+                    pos,
+                    id,
+                    in_subtree,
+                    on_path,
+                } => {
+                    let r = node.borrow();
+                    let has_child = r.children.iter().any(|c| c.is_some());
+                    match r.children[pos].as_ref() {
+                        Some(child) => {
+                            let highlight =
+                                in_subtree || on_path && self.path.contains(&Rc::as_ptr(child));
+                            stack.push(Step::Node {
+                                node: child.clone(),
+                                in_subtree,
+                                parent: Some((id, highlight)),
+                            });
+                        }
+                        None if self.options.nil_leaves => {
+                            let nil_id = self.next_id("nil");
+                            let _ = writeln!(
+                                self.out,
+                                "    {} [label=\"NIL\", shape=box, fillcolor=black, fontsize=8, width=0.3, height=0.2];",
+                                nil_id
+                            );
+                            self.edge(&id, &nil_id, in_subtree);
+                        }
+                        None if has_child => {
+                            // keeps the only child on its side
+                            let nil_id = self.next_id("nil");
+                            let _ = writeln!(self.out, "    {} [style=invis];", nil_id);
+                            let _ = writeln!(self.out, "    {} -> {} [style=invis];", id, nil_id);
+                        }
+                        None => {}
+                    }
+                }
+                Step::Edge {
+                    from,
+                    to,
+                    highlight,
+                } => self.edge(&from, &to, highlight),
+            }
+        }
+    }
+
+    // writes the node alone, returns its id and whether it's highlighted
+    // as part of the subtree and the search path
+    fn node(&mut self, node: &Node<T>, mut in_subtree: bool) -> (String, bool, bool) {
+        let id = self.next_id("n");
+        let r = node.borrow();
+
+        in_subtree |= self.subtree == Some(Rc::as_ptr(node));
+        let on_path = self.path.contains(&Rc::as_ptr(node));
+
+        let fill = match r.color {
+            Color::Red => "red",
+            Color::Black => "black",
+        };
+        let label = r.val.to_string().replace('\\', "\\\\").replace('"', "\\\"");
+        let _ = write!(
+            self.out,
+            "    {} [label=\"{}\", fillcolor={}",
+            id, label, fill
+        );
+        if on_path || in_subtree {
+            let _ = write!(self.out, ", color=\"{}\", penwidth=4", HIGHLIGHT);
+        }
+        self.out += "];\n";
+        (id, in_subtree, on_path)
+    }
+
+    fn edge(&mut self, from: &str, to: &str, highlight: bool) {
+        let _ = write!(self.out, "    {} -> {}", from, to);
+        if highlight {
+            let _ = write!(self.out, " [color=\"{}\", penwidth=3]", HIGHLIGHT);
+        }
+        self.out += ";\n";
+    }
+
+    fn next_id(&mut self, prefix: &str) -> String {
+        self.ids += 1;
+        format!("{}{}", prefix, self.ids - 1)
+    }
+}
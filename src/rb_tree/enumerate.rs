@@ -0,0 +1,185 @@
+use super::{node::*, RbTree};
+
+// every valid RbTree with values 1..=n, each shape with each coloring once
+//
+// trees are counted by size, black height and root color first, then the
+// tree number i is built right away from the counts, so nothing is stored
+// and any tree could be taken with `nth`
+//
+//   for mut t in ValidTrees::new(5) {
+//       t.remove(&3);
+//       assert!(t.validate().is_ok());
+//   }
+pub struct ValidTrees {
+    n: usize,
+    counts: Counts,
+    next: u128,
+    total: u128,
+}
+
+// numbers of trees by [size][black height], NIL is a BLACK tree of size 0
+// and height 0, a RED root doesn't add to the height
+struct Counts {
+    black: Vec<Vec<u128>>,
+    red: Vec<Vec<u128>>,
+}
+
+impl Counts {
+    fn new(n: usize) -> Self {
+        // a black height is never more than the number of nodes
+        let heights = n + 1;
+        let mut counts = Counts {
+            black: vec![vec![0; heights]; n + 1],
+            red: vec![vec![0; heights]; n + 1],
+        };
+        counts.black[0][0] = 1;
+
+        for size in 1..=n {
+            for h in 0..heights {
+                for left in 0..size {
+                    let right = size - 1 - left;
+                    // a RED node has BLACK children of the same height
+                    counts.red[size][h] = add(
+                        counts.red[size][h],
+                        mul(counts.black[left][h], counts.black[right][h]),
+                    );
+                    // a BLACK node has children of any color one level lower
+                    if h > 0 {
+                        counts.black[size][h] = add(
+                            counts.black[size][h],
+                            mul(counts.any(left, h - 1), counts.any(right, h - 1)),
+                        );
+                    }
+                }
+            }
+        }
+        counts
+    }
+
+    fn get(&self, color: Color, size: usize, h: usize) -> u128 {
+        match color {
+            Color::Black => self.black[size][h],
+            Color::Red => self.red[size][h],
+        }
+    }
+
+    fn any(&self, size: usize, h: usize) -> u128 {
+        add(self.black[size][h], self.red[size][h])
+    }
+
+    // the tree number `index` among trees of the size, the height and the root color,
+    // the values start from `first`
+    //
+    // recursive, the depth is the height of a valid tree
+    fn build(
+        &self,
+        color: Color,
+        size: usize,
+        h: usize,
+        mut index: u128,
+        first: i32,
+    ) -> Option<Node<i32>> {
+        if size == 0 {
+            return None;
+        }
+
+        let colors = match color {
+            Color::Red => vec![(Color::Black, Color::Black)],
+            Color::Black => vec![
+                (Color::Black, Color::Black),
+                (Color::Black, Color::Red),
+                (Color::Red, Color::Black),
+                (Color::Red, Color::Red),
+            ],
+        };
+        let child_h = match color {
+            Color::Red => h,
+            Color::Black => h - 1,
+        };
+
+        for left in 0..size {
+            let right = size - 1 - left;
+            for &(left_color, right_color) in colors.iter() {
+                let rights = self.get(right_color, right, child_h);
+                let count = mul(self.get(left_color, left, child_h), rights);
+                if index >= count {
+                    index -= count;
+                    continue;
+                }
+
+                let val = first + left as i32;
+                let node = RbTree::new_node(val, color);
+                node.borrow_mut().children = [
+                    self.build(left_color, left, child_h, index / rights, first),
+                    self.build(right_color, right, child_h, index % rights, val + 1),
+                ];
+                return Some(node);
+            }
+        }
+        unreachable!("the index is less than the count")
+    }
+}
+
+fn add(a: u128, b: u128) -> u128 {
+    a.checked_add(b).expect("too many trees to count")
+}
+
+fn mul(a: u128, b: u128) -> u128 {
+    a.checked_mul(b).expect("too many trees to count")
+}
+
+impl ValidTrees {
+    // panics if the numbers don't fit in u128, it happens from n = 123
+    pub fn new(n: usize) -> Self {
+        let counts = Counts::new(n);
+        let total = counts.black[n].iter().copied().fold(0, add);
+        ValidTrees {
+            n,
+            counts,
+            next: 0,
+            total,
+        }
+    }
+
+    // the number of all trees, including the ones already taken
+    pub fn total(&self) -> u128 {
+        self.total
+    }
+}
+
+impl Iterator for ValidTrees {
+    type Item = RbTree<i32>;
+
+    fn next(&mut self) -> Option<RbTree<i32>> {
+        if self.next >= self.total {
+            return None;
+        }
+        let mut index = self.next;
+        self.next += 1;
+
+        // the root is BLACK, trees go by black height
+        let mut h = 0;
+        while index >= self.counts.black[self.n][h] {
+            index -= self.counts.black[self.n][h];
+            h += 1;
+        }
+
+        let mut tree = RbTree::default();
+        tree.root = self.counts.build(Color::Black, self.n, h, index, 1);
+        tree.len = self.n;
+        Some(tree)
+    }
+
+    fn nth(&mut self, n: usize) -> Option<RbTree<i32>> {
+        self.next = self.next.saturating_add(n as u128);
+        self.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.total - self.next;
+        match usize::try_from(left) {
+            Ok(left) => (left, Some(left)),
+            Err(_) => (usize::MAX, None),
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use std::fmt;
+
+use super::{node::*, validate::PathDisplay, Path, PrintOptions, RbTree, Side, Violation};
+
+// bigger trees are shown only around the offending node
+const FULL_TREE: usize = 63;
+// levels shown above and below the offending node of a big tree
+const AROUND: usize = 2;
+// NIL paths listed one by one for different black heights
+const MAX_PATHS: usize = 16;
+// violations explained by `explain`, the rest are only counted
+const MAX_EXPLAINED: usize = 8;
+
+impl<T> Violation<T> {
+    // the broken rule in plain words, see the header of `RbTree`
+    pub fn rule(&self) -> &'static str {
+        match self {
+            Violation::RedRoot { .. } => "the root is BLACK",
+            Violation::RedRed { .. } => {
+                "there are no two consecutive RED nodes, a RED node has only BLACK children"
+            }
+            Violation::BlackHeightMismatch { .. } => {
+                "numbers of BLACK levels in left and right subtrees are the same, \
+                 so every path from a node down to a NIL has as many BLACK nodes"
+            }
+            Violation::OrderViolation { .. } => {
+                "it's a search tree, values on the left are not greater than the node \
+                 and values on the right are not less"
+            }
+            Violation::LenMismatch { .. } => "the length is the number of nodes in the tree",
+            Violation::HeightBoundExceeded { .. } => {
+                "the main rules keep the height within 2*log2(n+1), one of them is broken"
+            }
+            Violation::RightLeaningRed { .. } => {
+                "a left-leaning tree has RED nodes only as left children"
+            }
+        }
+    }
+}
+
+impl<T> RbTree<T>
+where
+    T: std::fmt::Debug + std::cmp::Ord + std::cmp::Eq + std::fmt::Display + Clone,
+{
+    // every broken rule with the tree and the offending nodes marked,
+    // None for a valid tree
+    pub fn explain(&self) -> Option<String> {
+        self.explanation(&self.validate().err()?)
+    }
+}
+
+impl<T> RbTree<T>
+where
+    T: std::fmt::Debug + std::cmp::Ord + std::cmp::Eq + std::fmt::Display,
+{
+    // the first `MAX_EXPLAINED` violations explained, None if there are none
+    pub(super) fn explanation<V: fmt::Display>(
+        &self,
+        violations: &[Violation<V>],
+    ) -> Option<String> {
+        if violations.is_empty() {
+            return None;
+        }
+        let mut out = String::new();
+        let shown = violations.len().min(MAX_EXPLAINED);
+        self.explain_violations(&mut out, &violations[..shown], &PrintOptions::default())
+            .unwrap();
+        if violations.len() > shown {
+            out.push_str(&format!(
+                "\n... and {} more violations\n",
+                violations.len() - shown
+            ));
+        }
+        Some(out)
+    }
+
+    // for violations given by `validate` or `validate_llrb` of this tree
+    //
+    // Two consecutive RED nodes, see val: 1 at root/L/L
+    // rule: there are no two consecutive RED nodes, a RED node has only BLACK children
+    // └─5b
+    //   └─3r ◀
+    //     └─1r ◀
+    pub fn explain_violations<V: fmt::Display, W: fmt::Write>(
+        &self,
+        out: &mut W,
+        violations: &[Violation<V>],
+        options: &PrintOptions<T>,
+    ) -> fmt::Result {
+        for (i, violation) in violations.iter().enumerate() {
+            if i > 0 {
+                out.write_char('\n')?;
+            }
+            writeln!(out, "{}", violation)?;
+            writeln!(out, "rule: {}", violation.rule())?;
+
+            let (value, path) = match violation.node() {
+                Some(node) => node,
+                None => continue,
+            };
+            // nodes from the root down to the offending one
+            let chain = match self.chain(path) {
+                Some(chain) => chain,
+                None => continue,
+            };
+            let node = chain[chain.len() - 1].clone();
+
+            let marked = match violation {
+                // the RED parent is a half of the problem
+                Violation::RedRed { .. } => chain[chain.len().saturating_sub(2)..].to_vec(),
+                // the longest path
+                Violation::HeightBoundExceeded { .. } => chain.clone(),
+                // ancestors the node is on the wrong side of
+                Violation::OrderViolation { .. } => chain
+                    .iter()
+                    .zip(path)
+                    .filter(|(a, side)| match side {
+                        Side::Left => node.borrow().val > a.borrow().val,
+                        Side::Right => node.borrow().val < a.borrow().val,
+                    })
+                    .map(|(a, _)| a.clone())
+                    .chain([node.clone()])
+                    .collect(),
+                _ => vec![node.clone()],
+            };
+
+            if self.len <= FULL_TREE {
+                self.render_marked(out, options, self.root.as_ref(), options.max_depth, marked)?;
+            } else {
+                let top = path.len().saturating_sub(AROUND);
+                if top > 0 {
+                    writeln!(out, "subtree at {}:", PathDisplay(&path[..top]))?;
+                }
+                let below = Some(path.len() - top + AROUND);
+                self.render_marked(out, options, Some(&chain[top]), below, marked)?;
+            }
+
+            if let Violation::BlackHeightMismatch { .. } = violation {
+                let above = chain[..chain.len() - 1]
+                    .iter()
+                    .filter(|n| n.borrow().color == Color::Black)
+                    .count();
+                writeln!(
+                    out,
+                    "BLACK nodes on paths from the root to NIL below val: {}",
+                    value
+                )?;
+                let paths: Vec<_> = nil_paths(&node, path, above)
+                    .into_iter()
+                    .map(|(p, count, others)| (PathDisplay(&p).to_string(), count, others))
+                    .collect();
+                let width = paths.iter().map(|(p, _, _)| p.len()).max().unwrap_or(0);
+                for (p, count, others) in paths {
+                    // padded by hand, `{:<width$}` panics on widths over u16::MAX
+                    let pad = " ".repeat(width - p.len());
+                    write!(out, "  {}{}  {}", p, pad, count)?;
+                    if others > 0 {
+                        write!(out, ", {} more paths with {}", others, count)?;
+                    }
+                    out.write_char('\n')?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // nodes on the path starting from the root, None if there is no such node
+    fn chain(&self, path: &[Side]) -> Option<Vec<Node<T>>> {
+        let mut chain = vec![self.root.clone()?];
+        for side in path {
+            let next = chain.last().unwrap().borrow().children[side.pos()].clone()?;
+            chain.push(next);
+        }
+        Some(chain)
+    }
+}
+
+// paths to NIL nodes in the subtree with numbers of BLACK nodes on them,
+// every path of a small subtree, otherwise the first path for every number
+// and how many other paths have it, up to `MAX_PATHS` numbers
+fn nil_paths<T>(node: &Node<T>, path: &[Side], above: usize) -> Vec<(Path, usize, usize)> {
+    let mut path = path.to_vec();
+    let mut all = Vec::new();
+    let mut groups: Vec<(Path, usize, usize)> = Vec::new();
+
+    // Some((node or NIL, side, BLACK nodes above)) goes down, None goes back up
+    let mut stack = vec![Some((Some(node.clone()), None, above))];
+    while let Some(step) = stack.pop() {
+        let (node, side, above) = match step {
+            Some(step) => step,
+            None => {
+                path.pop();
+                continue;
+            }
+        };
+        if let Some(side) = side {
+            path.push(side);
+            stack.push(None);
+        }
+
+        let node = match node {
+            Some(node) => node,
+            None => {
+                if all.len() <= MAX_PATHS {
+                    all.push((path.clone(), above, 0));
+                }
+                let full = groups.len() == MAX_PATHS;
+                match groups.iter_mut().find(|(_, count, _)| *count == above) {
+                    Some((_, _, others)) => *others += 1,
+                    None if !full => groups.push((path.clone(), above, 0)),
+                    None => {}
+                }
+                continue;
+            }
+        };
+        let r = node.borrow();
+        let count = above + (r.color == Color::Black) as usize;
+        for side in [Side::Right, Side::Left] {
+            stack.push(Some((r.children[side.pos()].clone(), Some(side), count)));
+        }
+    }
+
+    if all.len() <= MAX_PATHS {
+        all
+    } else {
+        groups
+    }
+}
@@ -0,0 +1,80 @@
+use std::ops::{Bound, RangeBounds};
+
+use super::{ancestor::Pos, node::*, RbTree};
+
+// values in ascending order, they are cloned because nodes are behind RefCell
+// and a reference can't outlive the borrow
+pub struct Iter<T> {
+    // the next node is on top, nodes below are its ancestors not visited yet
+    stack: Vec<Node<T>>,
+    end: Bound<T>,
+}
+
+impl<T: Ord + Clone> Iterator for Iter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let node = self.stack.pop()?;
+        let r = node.borrow();
+        if !before_end(&r.val, self.end.as_ref()) {
+            self.stack.clear();
+            return None;
+        }
+
+        // the right subtree goes next, starting from its minimum
+        let mut next = r.children[Pos::RIGHT].clone();
+        while let Some(n) = next {
+            next = n.borrow().children[Pos::LEFT].clone();
+            self.stack.push(n);
+        }
+        Some(r.val.clone())
+    }
+}
+
+// the value is not below the start of a range
+pub(crate) fn after_start<T: Ord>(val: &T, start: Bound<&T>) -> bool {
+    match start {
+        Bound::Included(s) => val >= s,
+        Bound::Excluded(s) => val > s,
+        Bound::Unbounded => true,
+    }
+}
+
+// the value is not above the end of a range
+pub(crate) fn before_end<T: Ord>(val: &T, end: Bound<&T>) -> bool {
+    match end {
+        Bound::Included(e) => val <= e,
+        Bound::Excluded(e) => val < e,
+        Bound::Unbounded => true,
+    }
+}
+
+impl<T> RbTree<T>
+where
+    T: std::fmt::Debug + std::cmp::Ord + std::cmp::Eq + std::fmt::Display + Clone,
+{
+    pub fn iter(&self) -> Iter<T> {
+        self.range(..)
+    }
+
+    // values within the range in ascending order, duplicates included
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> Iter<T> {
+        let mut iter = Iter {
+            stack: Vec::new(),
+            end: range.end_bound().cloned(),
+        };
+
+        // keeps nodes in the range on the way down to the first one,
+        // subtrees below the start are skipped
+        let mut next = self.root.clone();
+        while let Some(node) = next {
+            if after_start(&node.borrow().val, range.start_bound()) {
+                next = node.borrow().children[Pos::LEFT].clone();
+                iter.stack.push(node);
+            } else {
+                next = node.borrow().children[Pos::RIGHT].clone();
+            }
+        }
+        iter
+    }
+}
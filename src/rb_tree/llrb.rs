@@ -0,0 +1,329 @@
+use std::{fmt, mem, rc::Rc};
+
+use super::{
+    ancestor::Pos, node::*, Event, Observer, Operation, RbTree, Rotation, TreeStats, Violation,
+};
+
+// Sedgewick's left-leaning red-black tree, a RED node is always a left child,
+// so every BLACK node with its RED child is a 3-node of a 2-3 tree
+//
+// it's an RbTree with one more rule, so printing, validation and stats
+// are taken from the inner tree
+#[derive(Debug, Clone)]
+pub struct LlrbTree<T> {
+    tree: RbTree<T>,
+}
+
+impl<T> Default for LlrbTree<T> {
+    fn default() -> Self {
+        LlrbTree {
+            tree: RbTree::default(),
+        }
+    }
+}
+
+impl<T> fmt::Display for LlrbTree<T>
+where
+    T: std::fmt::Debug + std::cmp::Ord + std::cmp::Eq + std::fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.tree.fmt(f)
+    }
+}
+
+impl<T> LlrbTree<T>
+where
+    T: std::fmt::Debug + std::cmp::Ord + std::cmp::Eq + std::fmt::Display + Clone,
+{
+    pub fn set_observer(&mut self, observer: Box<dyn Observer<T>>) -> Option<Box<dyn Observer<T>>> {
+        self.tree.set_observer(observer)
+    }
+
+    // all RbTree rules and no RED right children
+    pub fn validate(&self) -> Result<TreeStats, Vec<Violation<T>>> {
+        self.tree.validate_llrb()
+    }
+}
+
+impl<T> LlrbTree<T>
+where
+    T: std::fmt::Debug + std::cmp::Ord + std::cmp::Eq + std::fmt::Display,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // the inner tree for `render()`, `diagram()`, `stats()` and the rest
+    pub fn tree(&self) -> &RbTree<T> {
+        &self.tree
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.tree.clear();
+    }
+
+    pub fn contains(&self, val: &T) -> bool {
+        self.tree.contains(val)
+    }
+
+    pub fn for_each<F: FnMut(&T)>(&self, f: F) {
+        self.tree.for_each(f)
+    }
+
+    pub fn take_observer(&mut self) -> Option<Box<dyn Observer<T>>> {
+        self.tree.take_observer()
+    }
+
+    // functions below are recursive the same way as in the book, the depth
+    // is never more than the height of a balanced tree
+    //
+    // each of them returns the node which took place of `h`, `parent` is
+    // needed to relink it, None means the root, so the tree stays consistent
+    // for the observer after every step
+
+    pub fn add(&mut self, val: T) {
+        self.tree.emit(|| Event::Operation(Operation::Add));
+
+        match self.tree.root.clone() {
+            Some(root) => {
+                self.insert(None, &root, val, 1);
+            }
+            None => self.tree.root = Some(RbTree::new_node(val, Color::Black)),
+        }
+
+        let root = self.tree.root.clone().unwrap();
+        self.tree.set_color(&root, Color::Black);
+        self.tree.len += 1;
+    }
+
+    fn insert(&mut self, parent: Option<&Node<T>>, h: &Node<T>, val: T, depth: usize) -> Node<T> {
+        let dir = if val <= h.borrow().val {
+            Pos::LEFT
+        } else {
+            Pos::RIGHT
+        };
+        let child = h.borrow().children[dir].clone();
+
+        match child {
+            Some(child) => {
+                self.insert(Some(h), &child, val, depth + 1);
+            }
+            None => {
+                self.tree.emit(|| Event::Compare(depth));
+                h.borrow_mut().children[dir] = Some(RbTree::new_node(val, Color::Red));
+            }
+        }
+        self.balance(parent, h.clone())
+    }
+
+    pub fn remove(&mut self, val: &T) -> bool {
+        self.tree.emit(|| Event::Operation(Operation::Remove));
+
+        // the way down below expects the value to be in the tree
+        let path = self.tree.search_path(val);
+        if path.last().is_none_or(|n| n.borrow().val != *val) {
+            let compared = path.len();
+            self.tree.emit(|| Event::Compare(compared));
+            return false;
+        }
+
+        let root = self.tree.root.clone().unwrap();
+        if !RbTree::is_red(RbTree::child(&root, Pos::LEFT).as_ref())
+            && !RbTree::is_red(RbTree::child(&root, Pos::RIGHT).as_ref())
+        {
+            self.tree.set_color(&root, Color::Red);
+        }
+
+        let mut compared = 0;
+        self.delete(None, &root, val, &mut compared);
+        self.tree.emit(|| Event::Compare(compared));
+
+        if let Some(root) = self.tree.root.clone() {
+            self.tree.set_color(&root, Color::Black);
+        }
+        self.tree.len -= 1;
+        true
+    }
+
+    // keeps the current node or its left child RED on the way down,
+    // so the node to unlink is a RED leaf
+    fn delete(
+        &mut self,
+        parent: Option<&Node<T>>,
+        h: &Node<T>,
+        val: &T,
+        compared: &mut usize,
+    ) -> Option<Node<T>> {
+        *compared += 1;
+        let node = h.clone();
+        let mut h = h.clone();
+        // rotations below move the node down to the right, an equal value
+        // which takes its place is a duplicate and isn't the one to remove
+        let is_target = |h: &Node<T>| Rc::ptr_eq(h, &node) && *val == h.borrow().val;
+
+        if *val < h.borrow().val {
+            let left = RbTree::child(&h, Pos::LEFT).unwrap();
+            if !RbTree::is_red(Some(&left))
+                && !RbTree::is_red(RbTree::child(&left, Pos::LEFT).as_ref())
+            {
+                h = self.move_red_left(parent, &h);
+            }
+            let left = RbTree::child(&h, Pos::LEFT).unwrap();
+            self.delete(Some(&h), &left, val, compared);
+        } else {
+            if RbTree::is_red(RbTree::child(&h, Pos::LEFT).as_ref()) {
+                h = self.rotate(parent, &h, Pos::RIGHT);
+            }
+            if is_target(&h) && RbTree::child(&h, Pos::RIGHT).is_none() {
+                return self.unlink(parent, &h);
+            }
+
+            let right = RbTree::child(&h, Pos::RIGHT).unwrap();
+            if !RbTree::is_red(Some(&right))
+                && !RbTree::is_red(RbTree::child(&right, Pos::LEFT).as_ref())
+            {
+                h = self.move_red_right(parent, &h);
+            }
+
+            let right = RbTree::child(&h, Pos::RIGHT).unwrap();
+            if is_target(&h) {
+                // the successor value takes place of the value,
+                // the value goes to the successor node which is removed
+                let mut min = right.clone();
+                while let Some(left) = RbTree::child(&min, Pos::LEFT) {
+                    min = left;
+                }
+                // the successor is in the right subtree, so it is never `h` itself
+                mem::swap(&mut h.borrow_mut().val, &mut min.borrow_mut().val);
+                self.tree.emit_with(|copy| Event::SwapNodes {
+                    a: copy(&min.borrow().val),
+                    b: copy(&h.borrow().val),
+                });
+                self.delete_min(Some(&h), &right);
+            } else {
+                self.delete(Some(&h), &right, val, compared);
+            }
+        }
+        Some(self.balance(parent, h))
+    }
+
+    fn delete_min(&mut self, parent: Option<&Node<T>>, h: &Node<T>) -> Option<Node<T>> {
+        let left = match RbTree::child(h, Pos::LEFT) {
+            Some(left) => left,
+            None => return self.unlink(parent, h),
+        };
+
+        let mut h = h.clone();
+        if !RbTree::is_red(Some(&left)) && !RbTree::is_red(RbTree::child(&left, Pos::LEFT).as_ref())
+        {
+            h = self.move_red_left(parent, &h);
+        }
+        let left = RbTree::child(&h, Pos::LEFT).unwrap();
+        self.delete_min(Some(&h), &left);
+        Some(self.balance(parent, h))
+    }
+
+    // a leaf is removed without any fixing, it's RED or the root
+    fn unlink(&mut self, parent: Option<&Node<T>>, h: &Node<T>) -> Option<Node<T>> {
+        self.tree.replace_child(parent, h, None);
+        self.tree.emit_with(|copy| Event::Extract {
+            node: copy(&h.borrow().val),
+        });
+        None
+    }
+
+    // the left child and its left child are BLACK, borrows from the right sibling
+    fn move_red_left(&mut self, parent: Option<&Node<T>>, h: &Node<T>) -> Node<T> {
+        self.flip(h);
+        let right = RbTree::child(h, Pos::RIGHT).unwrap();
+        if RbTree::is_red(RbTree::child(&right, Pos::LEFT).as_ref()) {
+            self.rotate(Some(h), &right, Pos::RIGHT);
+            let top = self.rotate(parent, h, Pos::LEFT);
+            self.flip(&top);
+            return top;
+        }
+        h.clone()
+    }
+
+    // the right child and its left child are BLACK, borrows from the left sibling
+    fn move_red_right(&mut self, parent: Option<&Node<T>>, h: &Node<T>) -> Node<T> {
+        self.flip(h);
+        let left = RbTree::child(h, Pos::LEFT).unwrap();
+        if RbTree::is_red(RbTree::child(&left, Pos::LEFT).as_ref()) {
+            let top = self.rotate(parent, h, Pos::RIGHT);
+            self.flip(&top);
+            return top;
+        }
+        h.clone()
+    }
+
+    // restores the left-leaning shape on the way back up
+    fn balance(&mut self, parent: Option<&Node<T>>, h: Node<T>) -> Node<T> {
+        let mut h = h;
+        let red = |h: &Node<T>, pos| RbTree::is_red(RbTree::child(h, pos).as_ref());
+
+        // RED right child leans left
+        if red(&h, Pos::RIGHT) && !red(&h, Pos::LEFT) {
+            h = self.rotate(parent, &h, Pos::LEFT);
+        }
+        // two RED nodes in a row become a 4-node
+        if red(&h, Pos::LEFT) && RbTree::child(&h, Pos::LEFT).is_some_and(|l| red(&l, Pos::LEFT)) {
+            h = self.rotate(parent, &h, Pos::RIGHT);
+        }
+        // 4-node is split, the middle goes up
+        if red(&h, Pos::LEFT) && red(&h, Pos::RIGHT) {
+            self.flip(&h);
+        }
+        h
+    }
+
+    // rotates the node down to the dir side, its child from the other side
+    // takes its place and its color, the node becomes RED
+    //    h            x
+    //   / \          / \
+    //  a   x   ->   h   c
+    //     / \      / \
+    //    b   c    a   b
+    fn rotate(&mut self, parent: Option<&Node<T>>, h: &Node<T>, dir: usize) -> Node<T> {
+        let other = RbTree::<T>::opposite_pos(dir);
+        let x = RbTree::child(h, other).unwrap();
+        let rest = x.borrow_mut().children[dir].take();
+
+        h.borrow_mut().children[other] = rest;
+        x.borrow_mut().children[dir] = Some(h.clone());
+        self.tree.replace_child(parent, h, Some(x.clone()));
+
+        self.tree.emit_with(|copy| Event::Rotate {
+            dir: if dir == Pos::LEFT {
+                Rotation::Left
+            } else {
+                Rotation::Right
+            },
+            pivot: copy(&x.borrow().val),
+        });
+        let color = h.borrow().color;
+        self.tree.set_color(&x, color);
+        self.tree.set_color(h, Color::Red);
+        x
+    }
+
+    // inverts colors of the node and its children
+    fn flip(&mut self, h: &Node<T>) {
+        let children = h.borrow().children.clone();
+        for node in std::iter::once(h).chain(children.iter().flatten()) {
+            let color = match node.borrow().color {
+                Color::Red => Color::Black,
+                Color::Black => Color::Red,
+            };
+            self.tree.set_color(node, color);
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use std::{cell::RefCell, mem, rc::Rc};
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Color {
     Red,
     Black,
@@ -17,6 +17,7 @@ pub struct RbTreeNode<T> {
 
 impl<T> RbTreeNode<T> {
     // as alternative the tree can swap values instead of references and color
+    #[allow(dead_code)]
     fn swap(&mut self, other: *mut RbTreeNode<T>) {
         unsafe {
             mem::swap(&mut self.val, &mut (*other).val);
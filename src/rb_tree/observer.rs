@@ -0,0 +1,150 @@
+use std::{fmt, mem};
+
+use super::{node::*, RbTree};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Rotation {
+    Left,
+    Right,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Operation {
+    Add,
+    Remove,
+}
+
+// what happens inside the algorithms, values identify nodes
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Event<T> {
+    // starts every `add` and `remove` call, other events belong to it
+    Operation(Operation),
+    // number of values compared while searching for a place
+    Compare(usize),
+    // the pivot takes place of its parent
+    Rotate { dir: Rotation, pivot: T },
+    Recolor { node: T, from: Color, to: Color },
+    // numbered the same way as comments in `fix_insert`,
+    // top-down cases have the same numbers for the same changes
+    InsertCase(u8),
+    // numbered the same way as comments in `fix_remove`
+    RemoveCase(u8),
+    // the node to remove and its successor exchanged their places,
+    // top-down removal exchanges values with the predecessor instead
+    SwapNodes { a: T, b: T },
+    // the node is unlinked from the tree
+    Extract { node: T },
+}
+
+impl<T: fmt::Display> fmt::Display for Event<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::Operation(Operation::Add) => write!(f, "add"),
+            Event::Operation(Operation::Remove) => write!(f, "remove"),
+            Event::Compare(n) => write!(f, "{} comparisons", n),
+            Event::Rotate { dir, pivot } => write!(f, "rotate {:?} with pivot {}", dir, pivot),
+            Event::Recolor { node, from, to } => {
+                write!(f, "recolor {} {:?} -> {:?}", node, from, to)
+            }
+            Event::InsertCase(n) => write!(f, "insert case {}: {}", n, insert_case(*n)),
+            Event::RemoveCase(n) => write!(f, "remove case {}: {}", n, remove_case(*n)),
+            Event::SwapNodes { a, b } => write!(f, "swap {} with {}", a, b),
+            Event::Extract { node } => write!(f, "extract {}", node),
+        }
+    }
+}
+
+pub fn insert_case(n: u8) -> &'static str {
+    match n {
+        1 => "uncle is RED, recolor and move up",
+        2 => "uncle is BLACK, node and parent are on the same side, rotate grandparent",
+        3 => "uncle is BLACK, node and parent are on different sides, rotate twice",
+        _ => "unknown",
+    }
+}
+
+pub fn remove_case(n: u8) -> &'static str {
+    match n {
+        1 => "sibling is RED, rotate parent and keep fixing",
+        2 => "sibling is BLACK with BLACK nephews, recolor and move up",
+        3 => "sibling is BLACK, far nephew is RED, rotate parent",
+        4 => "sibling is BLACK, near nephew is RED, rotate twice",
+        _ => "unknown",
+    }
+}
+
+// receives events from inside the algorithms, the tree is consistent
+// at the moment of the call and could be inspected from the root
+pub trait Observer<T> {
+    fn on_event(&mut self, event: &Event<T>, root: Option<&Node<T>>);
+}
+
+// any closure could be an observer
+impl<T, F: FnMut(&Event<T>)> Observer<T> for F {
+    fn on_event(&mut self, event: &Event<T>, _root: Option<&Node<T>>) {
+        self(event)
+    }
+}
+
+impl<T> fmt::Debug for dyn Observer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Observer")
+    }
+}
+
+// events carry copies of values, the copy is taken when the observer is set,
+// so only trees with an observer need `T: Clone`
+#[derive(Debug)]
+pub(super) struct Listener<T> {
+    observer: Box<dyn Observer<T>>,
+    copy: fn(&T) -> T,
+}
+
+impl<T> RbTree<T>
+where
+    T: std::fmt::Debug + std::cmp::Ord + std::cmp::Eq + std::fmt::Display + Clone,
+{
+    // returns the previous observer if any
+    pub fn set_observer(&mut self, observer: Box<dyn Observer<T>>) -> Option<Box<dyn Observer<T>>> {
+        let listener = Listener {
+            observer,
+            copy: T::clone,
+        };
+        self.observer.replace(listener).map(|l| l.observer)
+    }
+}
+
+impl<T> RbTree<T>
+where
+    T: std::fmt::Debug + std::cmp::Ord + std::cmp::Eq + std::fmt::Display,
+{
+    pub fn take_observer(&mut self) -> Option<Box<dyn Observer<T>>> {
+        self.observer.take().map(|l| l.observer)
+    }
+
+    // events are built only if somebody listens
+    #[inline]
+    pub(super) fn emit<F: FnOnce() -> Event<T>>(&mut self, event: F) {
+        self.emit_with(|_| event())
+    }
+
+    // for events with values, they are copied with the given function
+    #[inline]
+    pub(super) fn emit_with<F: FnOnce(fn(&T) -> T) -> Event<T>>(&mut self, event: F) {
+        if let Some(l) = self.observer.as_mut() {
+            l.observer.on_event(&event(l.copy), self.root.as_ref());
+        }
+    }
+
+    #[inline]
+    pub(super) fn set_color(&mut self, node: &Node<T>, color: Color) {
+        let from = mem::replace(&mut node.borrow_mut().color, color);
+        if from != color {
+            self.emit_with(|copy| Event::Recolor {
+                node: copy(&node.borrow().val),
+                from,
+                to: color,
+            });
+        }
+    }
+}
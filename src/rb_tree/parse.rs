@@ -0,0 +1,307 @@
+use std::{fmt, str::FromStr};
+
+use super::{node::*, RbTree, Side};
+
+// Two text forms of a tree with its exact shape and colors:
+//
+// - the output of `print`, Unicode or ASCII glyphs, `b` and `r` suffixes,
+//   focus markers are ignored
+//
+//   └─5b
+//     ├─7b
+//     └─3r
+//       ├─4b
+//       └─1b
+//
+// - brackets, a node with children is `(value left right)`, a missing
+//   child is `_`, a single child is the left one
+//
+//   (5b (3r 1b 4b) 7b)
+//
+// the tree isn't checked, so a broken one could be built and validated
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ParseError {
+    // the line doesn't start with tree glyphs
+    BadLine { line: usize },
+    // the line is more than one level deeper than the line above
+    MissingParent { line: usize },
+    // the parent already has a child on this side, or the tree has a root
+    SlotTaken { line: usize },
+    // the tree was printed with `max_depth`, the nodes below are lost
+    Truncated { line: usize },
+    // a node must be a value followed by `b` or `r`
+    BadNode { token: String },
+    Unexpected { offset: usize, token: String },
+    TooManyChildren { offset: usize },
+    UnexpectedEnd,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::BadLine { line } => write!(f, "line {} is not a tree line", line),
+            ParseError::MissingParent { line } => {
+                write!(f, "line {} has no parent on the level above", line)
+            }
+            ParseError::SlotTaken { line } => {
+                write!(f, "line {} goes to a place taken by another node", line)
+            }
+            ParseError::Truncated { line } => {
+                write!(f, "line {} is an ellipsis, the tree is cut", line)
+            }
+            ParseError::BadNode { token } => {
+                write!(f, "invalid node {:?}, expected a value with b or r", token)
+            }
+            ParseError::Unexpected { offset, token } => {
+                write!(f, "unexpected {:?} at offset {}", token, offset)
+            }
+            ParseError::TooManyChildren { offset } => {
+                write!(f, "a node has more than 2 children at offset {}", offset)
+            }
+            ParseError::UnexpectedEnd => write!(f, "unexpected end of input"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+const INDENTS: [&str; 3] = ["  ", "│ ", "| "];
+const LEFT_GLYPHS: [&str; 2] = ["└─", "`-"];
+const RIGHT_GLYPHS: [&str; 2] = ["├─", "|-"];
+const MARKERS: [&str; 2] = [" ◀", " <-"];
+const ELLIPSES: [&str; 2] = ["…", "..."];
+
+fn strip_any<'s>(s: &'s str, prefixes: &[&str]) -> Option<&'s str> {
+    prefixes.iter().find_map(|p| s.strip_prefix(p))
+}
+
+impl<T> RbTree<T>
+where
+    T: std::fmt::Debug + std::cmp::Ord + std::cmp::Eq + std::fmt::Display + FromStr,
+{
+    // reads the output of `print`, every level of depth is 2 characters
+    pub fn from_printed(s: &str) -> Result<Self, ParseError> {
+        let mut tree = RbTree::default();
+        // the last node seen on every level down to the current one
+        let mut levels: Vec<Node<T>> = Vec::new();
+
+        for (n, line) in s.lines().enumerate() {
+            let line_no = n + 1;
+            let mut rest = line.trim_end();
+            if rest.is_empty() {
+                continue;
+            }
+
+            let mut depth = 0;
+            while let Some(r) = strip_any(rest, &INDENTS) {
+                rest = r;
+                depth += 1;
+            }
+            let side = if let Some(r) = strip_any(rest, &LEFT_GLYPHS) {
+                rest = r;
+                Side::Left
+            } else if let Some(r) = strip_any(rest, &RIGHT_GLYPHS) {
+                rest = r;
+                Side::Right
+            } else {
+                return Err(ParseError::BadLine { line: line_no });
+            };
+
+            if ELLIPSES.contains(&rest) {
+                return Err(ParseError::Truncated { line: line_no });
+            }
+            for marker in MARKERS {
+                rest = rest.strip_suffix(marker).unwrap_or(rest);
+            }
+            let node = Self::parse_node(rest)?;
+
+            if depth > levels.len() {
+                return Err(ParseError::MissingParent { line: line_no });
+            }
+            levels.truncate(depth);
+            match levels.last() {
+                Some(parent) => {
+                    let slot = &mut parent.borrow_mut().children[side.pos()];
+                    if slot.is_some() {
+                        return Err(ParseError::SlotTaken { line: line_no });
+                    }
+                    *slot = Some(node.clone());
+                }
+                None if tree.root.is_some() => return Err(ParseError::SlotTaken { line: line_no }),
+                None => tree.root = Some(node.clone()),
+            }
+            levels.push(node);
+            tree.len += 1;
+        }
+        Ok(tree)
+    }
+
+    // reads `(5b (3r 1b 4b) 7b)`, uses explicit stack instead of recursion
+    pub fn from_brackets(s: &str) -> Result<Self, ParseError> {
+        let mut tree = RbTree::default();
+        let mut finished = false;
+        // open nodes with the number of children read so far
+        let mut stack: Vec<(Node<T>, usize)> = Vec::new();
+
+        let mut tokens = tokenize(s);
+        while let Some((offset, token)) = tokens.next() {
+            if finished {
+                return Err(ParseError::Unexpected {
+                    offset,
+                    token: token.to_string(),
+                });
+            }
+
+            let (node, opened) = match token {
+                "(" => match tokens.next() {
+                    Some((_, t)) if t != "(" && t != ")" && t != "_" => {
+                        (Some(Self::parse_node(t)?), true)
+                    }
+                    Some((offset, t)) => {
+                        return Err(ParseError::Unexpected {
+                            offset,
+                            token: t.to_string(),
+                        })
+                    }
+                    None => return Err(ParseError::UnexpectedEnd),
+                },
+                ")" => {
+                    if stack.pop().is_none() {
+                        return Err(ParseError::Unexpected {
+                            offset,
+                            token: token.to_string(),
+                        });
+                    }
+                    finished = stack.is_empty();
+                    continue;
+                }
+                "_" => (None, false),
+                _ => (Some(Self::parse_node(token)?), false),
+            };
+
+            match stack.last_mut() {
+                Some((parent, count)) => {
+                    if *count == 2 {
+                        return Err(ParseError::TooManyChildren { offset });
+                    }
+                    parent.borrow_mut().children[*count] = node.clone();
+                    *count += 1;
+                }
+                None => {
+                    tree.root = node.clone();
+                    finished = !opened;
+                }
+            }
+            tree.len += node.is_some() as usize;
+            if let Some(node) = node.filter(|_| opened) {
+                stack.push((node, 0));
+            }
+        }
+
+        if !stack.is_empty() {
+            return Err(ParseError::UnexpectedEnd);
+        }
+        Ok(tree)
+    }
+
+    fn parse_node(token: &str) -> Result<Node<T>, ParseError> {
+        let bad = || ParseError::BadNode {
+            token: token.to_string(),
+        };
+        let (val, color) = if let Some(v) = token.strip_suffix('b') {
+            (v, Color::Black)
+        } else if let Some(v) = token.strip_suffix('r') {
+            (v, Color::Red)
+        } else {
+            return Err(bad());
+        };
+        let val = val.parse::<T>().map_err(|_| bad())?;
+        Ok(Self::new_node(val, color))
+    }
+}
+
+// (offset, token), tokens are parentheses and runs of anything else
+// between them and whitespace
+fn tokenize(s: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut rest = s.char_indices().peekable();
+    std::iter::from_fn(move || {
+        while rest.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+        let (start, c) = rest.next()?;
+        let mut end = start + c.len_utf8();
+        if c != '(' && c != ')' {
+            while let Some((i, c)) =
+                rest.next_if(|(_, c)| !c.is_whitespace() && *c != '(' && *c != ')')
+            {
+                end = i + c.len_utf8();
+            }
+        }
+        Some((start, &s[start..end]))
+    })
+}
+
+impl<T> RbTree<T>
+where
+    T: std::fmt::Debug + std::cmp::Ord + std::cmp::Eq + std::fmt::Display,
+{
+    // the bracket form which `from_brackets` reads back, `_` for an empty tree,
+    // values are written with Display to be read with FromStr, so they must
+    // have no whitespace and parentheses
+    pub fn to_brackets(&self) -> String {
+        enum Item<T> {
+            Node(Option<Node<T>>),
+            Text(&'static str),
+        }
+
+        let mut out = String::new();
+        let mut stack = vec![Item::Node(self.root.clone())];
+        while let Some(item) = stack.pop() {
+            let node = match item {
+                Item::Text(text) => {
+                    out.push_str(text);
+                    continue;
+                }
+                Item::Node(None) => {
+                    out.push('_');
+                    continue;
+                }
+                Item::Node(Some(node)) => node,
+            };
+
+            let r = node.borrow();
+            let color = if r.color == Color::Red { 'r' } else { 'b' };
+            let [left, right] = r.children.clone();
+            if left.is_none() && right.is_none() {
+                out.push_str(&format!("{}{}", r.val, color));
+                continue;
+            }
+
+            out.push_str(&format!("({}{}", r.val, color));
+            stack.push(Item::Text(")"));
+            if right.is_some() {
+                stack.push(Item::Node(right));
+                stack.push(Item::Text(" "));
+            }
+            stack.push(Item::Node(left));
+            stack.push(Item::Text(" "));
+        }
+        out
+    }
+}
+
+// either form, the printed one starts with a glyph
+impl<T> FromStr for RbTree<T>
+where
+    T: std::fmt::Debug + std::cmp::Ord + std::cmp::Eq + std::fmt::Display + FromStr,
+{
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        let start = s.trim_start();
+        if strip_any(start, &LEFT_GLYPHS).is_some() || strip_any(start, &RIGHT_GLYPHS).is_some() {
+            Self::from_printed(s)
+        } else {
+            Self::from_brackets(s)
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use std::{cell::RefCell, fmt, io, rc::Rc, thread, time::Duration};
+
+use super::{copy_tree, node::*, Event, Observer, PrintOptions, RbTree};
+
+const ANSI_CLEAR: &str = "\x1b[2J\x1b[H";
+
+// the tree in between two steps of an algorithm
+pub struct Frame<T> {
+    // the fixup case the step belongs to
+    pub case: Option<Event<T>>,
+    pub caption: String,
+    pub tree: RbTree<T>,
+    // values of the nodes touched by the step
+    pub changed: Vec<T>,
+}
+
+// all states of the tree during one operation, the first frame is the tree
+// before the operation and the last one is the tree after it
+pub struct Recording<T> {
+    pub title: String,
+    pub frames: Vec<Frame<T>>,
+}
+
+struct State<T> {
+    frames: Vec<Frame<T>>,
+    case: Option<Event<T>>,
+    // the observer the tree had before recording, it keeps receiving events
+    inner: Option<Box<dyn Observer<T>>>,
+}
+
+struct Recorder<T>(Rc<RefCell<State<T>>>);
+
+impl<T: Clone + fmt::Display> Observer<T> for Recorder<T> {
+    fn on_event(&mut self, event: &Event<T>, root: Option<&Node<T>>) {
+        let mut state = self.0.borrow_mut();
+        if let Some(inner) = state.inner.as_mut() {
+            inner.on_event(event, root);
+        }
+
+        let changed = match event {
+            Event::InsertCase(_) | Event::RemoveCase(_) => {
+                state.case = Some(event.clone());
+                return;
+            }
+            // nothing changes in the tree
+            Event::Operation(_) | Event::Compare(_) => return,
+            Event::Rotate { pivot, .. } => vec![pivot.clone()],
+            Event::Recolor { node, .. } => vec![node.clone()],
+            Event::SwapNodes { a, b } => vec![a.clone(), b.clone()],
+            Event::Extract { .. } => Vec::new(),
+        };
+        let frame = Frame {
+            case: state.case.clone(),
+            caption: event.to_string(),
+            tree: snapshot(root),
+            changed,
+        };
+        state.frames.push(frame);
+    }
+}
+
+fn snapshot<T: Clone>(root: Option<&Node<T>>) -> RbTree<T> {
+    let mut tree = RbTree::default();
+    tree.root = root.map(copy_tree);
+    // the length counter of the original tree isn't updated in the middle of operation
+    let mut next = tree.root.clone().into_iter().collect::<Vec<_>>();
+    while let Some(node) = next.pop() {
+        tree.len += 1;
+        next.extend(node.borrow().children.iter().flatten().cloned());
+    }
+    tree
+}
+
+impl<T> RbTree<T>
+where
+    T: std::fmt::Debug + std::cmp::Ord + std::cmp::Eq + std::fmt::Display + Clone + 'static,
+{
+    // the same as `add` but keeps every intermediate state of the tree
+    pub fn add_recorded(&mut self, val: T) -> Recording<T> {
+        let title = format!("add {:?}", val);
+        let added = vec![val.clone()];
+        self.record(title, Vec::new(), added, |t| t.add(val))
+    }
+
+    // the same as `remove` but keeps every intermediate state of the tree
+    pub fn remove_recorded(&mut self, val: &T) -> (bool, Recording<T>) {
+        let mut removed = false;
+        let title = format!("remove {:?}", val);
+        let recording = self.record(title, vec![val.clone()], Vec::new(), |t| {
+            removed = t.remove(val)
+        });
+        (removed, recording)
+    }
+
+    fn record<F: FnOnce(&mut Self)>(
+        &mut self,
+        title: String,
+        before: Vec<T>,
+        after: Vec<T>,
+        operation: F,
+    ) -> Recording<T> {
+        let first = Frame {
+            case: None,
+            caption: "before".to_string(),
+            tree: snapshot(self.root.as_ref()),
+            changed: before,
+        };
+        let state = Rc::new(RefCell::new(State {
+            frames: vec![first],
+            case: None,
+            inner: self.take_observer(),
+        }));
+
+        self.set_observer(Box::new(Recorder(state.clone())));
+        operation(self);
+        self.take_observer();
+
+        let mut state = state.borrow_mut();
+        if let Some(inner) = state.inner.take() {
+            self.set_observer(inner);
+        }
+
+        let mut frames = std::mem::take(&mut state.frames);
+        frames.push(Frame {
+            case: None,
+            caption: "after".to_string(),
+            tree: snapshot(self.root.as_ref()),
+            changed: after,
+        });
+        Recording { title, frames }
+    }
+}
+
+impl<T> Frame<T>
+where
+    T: std::fmt::Debug + std::cmp::Ord + std::cmp::Eq + std::fmt::Display + Clone,
+{
+    // the case, the step and the tree with changed nodes marked
+    pub fn render<W: fmt::Write>(&self, out: &mut W, ansi: bool) -> fmt::Result {
+        if let Some(case) = self.case.as_ref() {
+            writeln!(out, "{}", case)?;
+        }
+        writeln!(out, "{}", self.caption)?;
+
+        let highlight = |v: &T| self.changed.contains(v);
+        self.tree.render(
+            out,
+            &PrintOptions {
+                ansi,
+                highlight: Some(&highlight),
+                ..Default::default()
+            },
+        )
+    }
+}
+
+impl<T> fmt::Display for Frame<T>
+where
+    T: std::fmt::Debug + std::cmp::Ord + std::cmp::Eq + std::fmt::Display + Clone,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.render(f, false)
+    }
+}
+
+impl<T> Recording<T>
+where
+    T: std::fmt::Debug + std::cmp::Ord + std::cmp::Eq + std::fmt::Display + Clone,
+{
+    // shows frames one by one in the terminal, every frame replaces the previous one
+    pub fn play<W: io::Write>(&self, mut out: W, delay: Duration) -> io::Result<()> {
+        for (i, frame) in self.frames.iter().enumerate() {
+            let mut s = String::from(ANSI_CLEAR);
+            s += &format!("{} - step {}/{}\n", self.title, i + 1, self.frames.len());
+            frame
+                .render(&mut s, true)
+                .map_err(|_| io::Error::other("formatting failed"))?;
+
+            out.write_all(s.as_bytes())?;
+            out.flush()?;
+            thread::sleep(delay);
+        }
+        Ok(())
+    }
+}
+
+// all frames one after another, without any terminal tricks
+impl<T> fmt::Display for Recording<T>
+where
+    T: std::fmt::Debug + std::cmp::Ord + std::cmp::Eq + std::fmt::Display + Clone,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, frame) in self.frames.iter().enumerate() {
+            writeln!(f, "{} - step {}/{}", self.title, i + 1, self.frames.len())?;
+            write!(f, "{}", frame)?;
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,211 @@
+use std::{fmt, io};
+
+use super::{ancestor::Pos, node::*, RbTree};
+
+const ANSI_RED: &str = "\x1b[31m";
+const ANSI_RESET: &str = "\x1b[0m";
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Glyphs {
+    Unicode,
+    Ascii,
+}
+
+impl Glyphs {
+    // (left child, right child, continuation, focus marker)
+    pub(super) fn set(self) -> (&'static str, &'static str, &'static str, &'static str) {
+        match self {
+            Glyphs::Unicode => ("└─", "├─", "│ ", " ◀"),
+            Glyphs::Ascii => ("`-", "|-", "| ", " <-"),
+        }
+    }
+
+    fn ellipsis(self) -> &'static str {
+        match self {
+            Glyphs::Unicode => "…",
+            Glyphs::Ascii => "...",
+        }
+    }
+}
+
+pub struct PrintOptions<'a, T> {
+    pub glyphs: Glyphs,
+    // paint RED nodes with ANSI colors instead of `b` and `r` suffixes
+    pub ansi: bool,
+    // deeper levels are replaced with an ellipsis
+    pub max_depth: Option<usize>,
+    // show only nodes around the value, not further than the distance in levels
+    pub zoom: Option<(&'a T, usize)>,
+    // used instead of `{:?}` to format values
+    pub format: Option<&'a dyn Fn(&T) -> String>,
+    // nodes to mark the same way as the zoomed value
+    pub highlight: Option<&'a dyn Fn(&T) -> bool>,
+}
+
+impl<'a, T> Default for PrintOptions<'a, T> {
+    fn default() -> Self {
+        PrintOptions {
+            glyphs: Glyphs::Unicode,
+            ansi: false,
+            max_depth: None,
+            zoom: None,
+            format: None,
+            highlight: None,
+        }
+    }
+}
+
+// renders the tree with options via `Display`
+pub struct Printed<'a, T> {
+    tree: &'a RbTree<T>,
+    options: PrintOptions<'a, T>,
+}
+
+impl<T> RbTree<T>
+where
+    T: std::fmt::Debug + std::cmp::Ord + std::cmp::Eq + std::fmt::Display,
+{
+    // writes the tree sideways, the right subtree is above the left one
+    // └─5b
+    //   ├─8b
+    //   └─3b
+    //     └─1r
+    pub fn render<W: fmt::Write>(&self, out: &mut W, options: &PrintOptions<T>) -> fmt::Result {
+        let mut root = self.root.clone();
+        let mut max_depth = options.max_depth;
+        let mut focus = None;
+
+        if let Some((val, radius)) = options.zoom {
+            let path = self.search_path(val);
+            let found = path.last().filter(|n| n.borrow().val == *val).cloned();
+
+            // start a few levels above the value
+            let top = path.len().saturating_sub(radius + 1);
+            root = path.get(top).cloned();
+
+            let below = path.len().saturating_sub(top + 1) + radius;
+            max_depth = Some(max_depth.map_or(below, |d| d.min(below)));
+            focus = found;
+        }
+
+        let printer = Printer {
+            options,
+            max_depth,
+            marked: focus.into_iter().collect(),
+        };
+        printer.write(out, root.as_ref())
+    }
+
+    // prints the subtree with the nodes marked the same way as the zoomed value
+    pub(super) fn render_marked<W: fmt::Write>(
+        &self,
+        out: &mut W,
+        options: &PrintOptions<T>,
+        root: Option<&Node<T>>,
+        max_depth: Option<usize>,
+        marked: Vec<Node<T>>,
+    ) -> fmt::Result {
+        let printer = Printer {
+            options,
+            max_depth,
+            marked,
+        };
+        printer.write(out, root)
+    }
+
+    pub fn render_io<W: io::Write>(&self, mut out: W, options: &PrintOptions<T>) -> io::Result<()> {
+        let mut s = String::new();
+        self.render(&mut s, options)
+            .map_err(|_| io::Error::other("formatting failed"))?;
+        out.write_all(s.as_bytes())
+    }
+
+    // `println!("{}", tree.display_with(options))`
+    pub fn display_with<'a>(&'a self, options: PrintOptions<'a, T>) -> Printed<'a, T> {
+        Printed {
+            tree: self,
+            options,
+        }
+    }
+}
+
+impl<T> fmt::Display for RbTree<T>
+where
+    T: std::fmt::Debug + std::cmp::Ord + std::cmp::Eq + std::fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.render(f, &PrintOptions::default())
+    }
+}
+
+impl<'a, T> fmt::Display for Printed<'a, T>
+where
+    T: std::fmt::Debug + std::cmp::Ord + std::cmp::Eq + std::fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.tree.render(f, &self.options)
+    }
+}
+
+struct Printer<'a, 'o, T> {
+    options: &'a PrintOptions<'o, T>,
+    max_depth: Option<usize>,
+    marked: Vec<Node<T>>,
+}
+
+impl<'a, 'o, T: std::fmt::Debug> Printer<'a, 'o, T> {
+    // uses explicit stack instead of recursion, the right child is pushed last
+    // so it's printed first
+    fn write<W: fmt::Write>(&self, out: &mut W, root: Option<&Node<T>>) -> fmt::Result {
+        let (left, right, cont, marker) = self.options.glyphs.set();
+        let mut prefix = String::new();
+
+        // (node, is left child, depth, length of the prefix in front of the node)
+        let mut stack = Vec::new();
+        if let Some(root) = root {
+            stack.push((root.clone(), true, 0, 0));
+        }
+
+        while let Some((node, is_left, depth, len)) = stack.pop() {
+            prefix.truncate(len);
+            out.write_str(&prefix)?;
+            out.write_str(if is_left { left } else { right })?;
+
+            if self.max_depth.is_some_and(|max| depth > max) {
+                writeln!(out, "{}", self.options.glyphs.ellipsis())?;
+                continue;
+            }
+
+            // print the value of the node
+            let r = node.borrow();
+            let val = match self.options.format {
+                Some(format) => format(&r.val),
+                None => format!("{:?}", r.val),
+            };
+            match (self.options.ansi, r.color) {
+                (true, Color::Red) => write!(out, "{}{}{}", ANSI_RED, val, ANSI_RESET)?,
+                (true, Color::Black) => out.write_str(&val)?,
+                (false, Color::Red) => write!(out, "{}r", val)?,
+                (false, Color::Black) => write!(out, "{}b", val)?,
+            }
+            if self.marked.iter().any(|m| Node::ptr_eq(m, &node))
+                || self.options.highlight.is_some_and(|h| h(&r.val))
+            {
+                out.write_str(marker)?;
+            }
+            out.write_char('\n')?;
+
+            // enter the next tree level - left and right branch
+            prefix.push_str(if is_left { "  " } else { cont });
+            let len = prefix.len();
+
+            if let Some(child) = r.children[Pos::LEFT].as_ref() {
+                stack.push((child.clone(), true, depth + 1, len));
+            }
+            if let Some(child) = r.children[Pos::RIGHT].as_ref() {
+                stack.push((child.clone(), false, depth + 1, len));
+            }
+        }
+        Ok(())
+    }
+}
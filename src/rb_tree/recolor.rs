@@ -0,0 +1,198 @@
+use std::fmt;
+
+use super::{node::*, validate::PathDisplay, Path, RbTree, Side};
+
+// colors for every node in preorder, the left subtree goes first
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Coloring {
+    pub black_height: usize,
+    pub colors: Vec<Color>,
+}
+
+// the lowest node whose subtrees could be colored one by one,
+// but never with equal black heights, so the whole tree can't be colored
+//
+// a path of k nodes has from k/2 to k BLACK nodes, so a subtree with
+// a short path and a subtree with a long one have no common black height
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Certificate<T> {
+    pub value: T,
+    pub path: Path,
+    // black heights each subtree could have
+    pub left: Vec<usize>,
+    pub right: Vec<usize>,
+    // the shortest and the longest path from the subtree root to a NIL, in nodes
+    pub left_paths: (usize, usize),
+    pub right_paths: (usize, usize),
+}
+
+impl<T: fmt::Display> fmt::Display for Certificate<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "No coloring exists, subtrees of val: {} at {} can't have the same black height, \
+             left: {:?} with paths to NIL of {} to {} nodes, \
+             right: {:?} with paths to NIL of {} to {} nodes",
+            self.value,
+            PathDisplay(&self.path),
+            self.left,
+            self.left_paths.0,
+            self.left_paths.1,
+            self.right,
+            self.right_paths.0,
+            self.right_paths.1
+        )
+    }
+}
+
+// heights are bits, a black height is never more than the shortest path
+// to NIL, and that is under 64 nodes in any tree which fits in memory
+type Heights = u64;
+
+// parent index and the side of the node in it, None for the root
+type Parent = Option<(usize, Side)>;
+
+struct Entry<T> {
+    node: Node<T>,
+    children: [Option<usize>; 2],
+    parent: Parent,
+    // black heights of the subtree if the node is BLACK and if it's RED
+    black: Heights,
+    red: Heights,
+    paths: (usize, usize),
+}
+
+fn heights(bits: Heights) -> Vec<usize> {
+    (0..Heights::BITS as usize)
+        .filter(|h| bits & (1 << h) != 0)
+        .collect()
+}
+
+impl<T> RbTree<T>
+where
+    T: std::fmt::Debug + std::cmp::Ord + std::cmp::Eq + std::fmt::Display + Clone,
+{
+    // finds colors which make the shape a valid RbTree, values aren't looked at
+    //
+    // goes bottom-up and keeps black heights every subtree could have:
+    // - NIL is BLACK with height 0
+    // - a RED node needs BLACK children of the same height
+    // - a BLACK node needs children of any color of the same height, plus one
+    // then goes top-down from the highest black height of the BLACK root
+    // picking a color for every child, BLACK when both fit
+    pub fn find_coloring(&self) -> Result<Coloring, Certificate<T>> {
+        let mut entries: Vec<Entry<T>> = Vec::new();
+
+        // preorder, so children are always after their parent
+        let mut stack: Vec<(Node<T>, Parent)> = Vec::new();
+        if let Some(root) = self.root.as_ref() {
+            stack.push((root.clone(), None));
+        }
+        while let Some((node, parent)) = stack.pop() {
+            let index = entries.len();
+            if let Some((p, side)) = parent {
+                entries[p].children[side.pos()] = Some(index);
+            }
+            for side in [Side::Right, Side::Left] {
+                if let Some(child) = node.borrow().children[side.pos()].as_ref() {
+                    stack.push((child.clone(), Some((index, side))));
+                }
+            }
+            entries.push(Entry {
+                node,
+                children: [None, None],
+                parent,
+                black: 0,
+                red: 0,
+                paths: (0, 0),
+            });
+        }
+
+        // bottom-up
+        for i in (0..entries.len()).rev() {
+            // (if BLACK, if RED, paths) of a child, NIL is BLACK with height 0
+            let child = |c: Option<usize>| {
+                c.map_or((1, 0, (0, 0)), |c| {
+                    let e = &entries[c];
+                    (e.black, e.red, e.paths)
+                })
+            };
+            let (left_black, left_red, left_paths) = child(entries[i].children[0]);
+            let (right_black, right_red, right_paths) = child(entries[i].children[1]);
+
+            let red = left_black & right_black;
+            let black = ((left_black | left_red) & (right_black | right_red)) << 1;
+
+            // nothing fits BLACK means nothing fits RED as well
+            if black == 0 {
+                let mut path = Vec::new();
+                let mut next = i;
+                while let Some((p, side)) = entries[next].parent {
+                    path.push(side);
+                    next = p;
+                }
+                path.reverse();
+
+                return Err(Certificate {
+                    value: entries[i].node.borrow().val.clone(),
+                    path,
+                    left: heights(left_black | left_red),
+                    right: heights(right_black | right_red),
+                    left_paths,
+                    right_paths,
+                });
+            }
+
+            let e = &mut entries[i];
+            e.black = black;
+            e.red = red;
+            e.paths = (
+                1 + left_paths.0.min(right_paths.0),
+                1 + left_paths.1.max(right_paths.1),
+            );
+        }
+
+        let black_height = match entries.first() {
+            Some(root) => (Heights::BITS - 1 - root.black.leading_zeros()) as usize,
+            None => 0,
+        };
+
+        // top-down, (color, black height) for every node
+        let mut wanted = vec![(Color::Black, black_height); entries.len()];
+        for i in 0..entries.len() {
+            let (color, height) = wanted[i];
+            for c in entries[i].children.iter().flatten() {
+                wanted[*c] = match color {
+                    Color::Red => (Color::Black, height),
+                    Color::Black if entries[*c].black & (1 << (height - 1)) != 0 => {
+                        (Color::Black, height - 1)
+                    }
+                    Color::Black => (Color::Red, height - 1),
+                };
+            }
+        }
+
+        Ok(Coloring {
+            black_height,
+            colors: wanted.into_iter().map(|(color, _)| color).collect(),
+        })
+    }
+
+    // paints the tree with the found coloring, observers see every change,
+    // returns the black height
+    pub fn recolor(&mut self) -> Result<usize, Certificate<T>> {
+        let coloring = self.find_coloring()?;
+
+        let mut colors = coloring.colors.into_iter();
+        let mut stack: Vec<Node<T>> = self.root.iter().cloned().collect();
+        while let Some(node) = stack.pop() {
+            self.set_color(&node, colors.next().unwrap());
+            for side in [Side::Right, Side::Left] {
+                if let Some(child) = node.borrow().children[side.pos()].as_ref() {
+                    stack.push(child.clone());
+                }
+            }
+        }
+        Ok(coloring.black_height)
+    }
+}
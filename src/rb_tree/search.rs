@@ -0,0 +1,346 @@
+use std::{cell::RefCell, collections::HashSet, fmt, rc::Rc};
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use super::{CostCounters, RbTree, Strategy};
+
+// what a worst case is the worst at
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Goal {
+    Height,
+    BlackHeight,
+    // the most rotations done by one operation of the sequence
+    Rotations,
+}
+
+impl Goal {
+    // the most a tree of n values could have, with the formula
+    pub fn bound(self, n: usize, strategy: Strategy) -> (f64, &'static str) {
+        let log = (n as f64 + 1.0).log2();
+        match (self, strategy) {
+            (Goal::Height, _) => (2.0 * log, "2*log2(n+1)"),
+            // n >= 2^bh - 1, every BLACK level is full
+            (Goal::BlackHeight, _) => (log, "log2(n+1)"),
+            (Goal::Rotations, Strategy::BottomUp) => (3.0, "2 for add, 3 for remove"),
+            // up to 2 on every level on the way down
+            (Goal::Rotations, Strategy::TopDown) => (4.0 * log, "2 per level, 4*log2(n+1)"),
+        }
+    }
+}
+
+impl fmt::Display for Goal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Goal::Height => write!(f, "height"),
+            Goal::BlackHeight => write!(f, "black height"),
+            Goal::Rotations => write!(f, "rotations per operation"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Step {
+    Add(i32),
+    Remove(i32),
+}
+
+// the same lines `rbtree run` reads
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Step::Add(v) => write!(f, "add {}", v),
+            Step::Remove(v) => write!(f, "remove {}", v),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SearchOptions {
+    pub goal: Goal,
+    pub strategy: Strategy,
+    // values added and removed again on top of n, trees hold up to n+extra values
+    pub extra: usize,
+    // every reachable tree is tried up to this n, random sequences are improved above it
+    pub exhaustive_up_to: usize,
+    pub iterations: usize,
+    pub seed: u64,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions {
+            goal: Goal::Height,
+            strategy: Strategy::BottomUp,
+            extra: 2,
+            exhaustive_up_to: 5,
+            iterations: 2000,
+            seed: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Found {
+    pub goal: Goal,
+    pub strategy: Strategy,
+    pub n: usize,
+    pub steps: Vec<Step>,
+    pub value: usize,
+    pub bound: f64,
+    pub formula: &'static str,
+    // every sequence was tried, so nothing is worse
+    pub exhaustive: bool,
+    // the tree after all steps
+    pub tree: RbTree<i32>,
+}
+
+// a file for `rbtree run`, the report and the tree are comments
+// # height 3 of 4 values, the bound is 4.64 = 2*log2(n+1)
+// # exhaustive search, BottomUp, 6 steps
+// # └─1b
+// # ...
+// add 0
+// ...
+impl fmt::Display for Found {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "# {} {} of {} values, the bound is {:.2} = {}",
+            self.goal, self.value, self.n, self.bound, self.formula
+        )?;
+        let search = if self.exhaustive {
+            "exhaustive"
+        } else {
+            "heuristic"
+        };
+        writeln!(
+            f,
+            "# {} search, {:?}, {} steps",
+            search,
+            self.strategy,
+            self.steps.len()
+        )?;
+        for line in self.tree.to_string().lines() {
+            writeln!(f, "# {}", line)?;
+        }
+        for step in &self.steps {
+            writeln!(f, "{}", step)?;
+        }
+        Ok(())
+    }
+}
+
+// a sequence of adds and removes which leaves n values in the tree
+// and gives the biggest value of the goal found
+//
+// up to `exhaustive_up_to` it goes breadth-first over every tree reachable
+// with distinct values from 0..n+extra, so the sequence is also the shortest,
+// above it random sequences are improved by swapping steps while the value
+// doesn't get worse
+pub fn worst_case(n: usize, options: &SearchOptions) -> Found {
+    let (steps, exhaustive) = if n <= options.exhaustive_up_to {
+        (exhaustive(n, options), true)
+    } else {
+        (heuristic(n, options), false)
+    };
+
+    let (value, _, tree) = play(&steps, options);
+    let size = match options.goal {
+        Goal::Rotations => n + options.extra,
+        _ => n,
+    };
+    let (bound, formula) = options.goal.bound(size, options.strategy);
+    Found {
+        goal: options.goal,
+        strategy: options.strategy,
+        n,
+        steps,
+        value,
+        bound,
+        formula,
+        exhaustive,
+        tree,
+    }
+}
+
+fn apply(tree: &mut RbTree<i32>, step: Step) {
+    match step {
+        Step::Add(v) => tree.add(v),
+        Step::Remove(v) => {
+            tree.remove(&v);
+        }
+    }
+}
+
+fn measure(tree: &RbTree<i32>, goal: Goal) -> usize {
+    match goal {
+        Goal::Height => tree.height(),
+        Goal::BlackHeight => tree.black_height(),
+        Goal::Rotations => 0,
+    }
+}
+
+// runs the steps from an empty tree, gives the value, a tie-break which leads
+// the search towards bigger values and the tree
+fn play(steps: &[Step], options: &SearchOptions) -> (usize, usize, RbTree<i32>) {
+    let mut tree = RbTree::with_strategy(options.strategy);
+    let counters = Rc::new(RefCell::new(CostCounters::default()));
+    if options.goal == Goal::Rotations {
+        tree.set_observer(Box::new(counters.clone()));
+    }
+
+    for step in steps {
+        apply(&mut tree, *step);
+    }
+    tree.take_observer();
+
+    let c = counters.borrow().snapshot();
+    let (value, tie_break) = match options.goal {
+        // deeper nodes on average
+        Goal::Height => {
+            let depths = tree.stats().depths;
+            let sum = depths.iter().enumerate().map(|(d, n)| d * n).sum();
+            (tree.height(), sum)
+        }
+        Goal::BlackHeight => (tree.black_height(), tree.stats().black),
+        Goal::Rotations => (
+            c.max_add.rotations.max(c.max_remove.rotations),
+            c.total().rotations,
+        ),
+    };
+    (value, tie_break, tree)
+}
+
+fn exhaustive(n: usize, options: &SearchOptions) -> Vec<Step> {
+    struct State {
+        tree: RbTree<i32>,
+        // the state before and the step from it
+        parent: Option<(usize, Step)>,
+    }
+
+    let values = (n + options.extra) as i32;
+    let mut states = vec![State {
+        tree: RbTree::with_strategy(options.strategy),
+        parent: None,
+    }];
+    let mut seen = HashSet::from([states[0].tree.to_brackets()]);
+    // (value, state, the step after it for rotations)
+    let mut best: Option<(usize, usize, Option<Step>)> = None;
+
+    let mut i = 0;
+    while i < states.len() {
+        if options.goal != Goal::Rotations && states[i].tree.len() == n {
+            let value = measure(&states[i].tree, options.goal);
+            if best.is_none_or(|(b, _, _)| value > b) {
+                best = Some((value, i, None));
+            }
+        }
+
+        for v in 0..values {
+            let step = if states[i].tree.contains(&v) {
+                Step::Remove(v)
+            } else {
+                Step::Add(v)
+            };
+
+            // a clone has no observer
+            let mut tree = states[i].tree.clone();
+            let counters = Rc::new(RefCell::new(CostCounters::default()));
+            if options.goal == Goal::Rotations {
+                tree.set_observer(Box::new(counters.clone()));
+            }
+            apply(&mut tree, step);
+            tree.take_observer();
+
+            // the sequence ends with this step, so it must leave n values
+            let value = counters.borrow().last.rotations;
+            if options.goal == Goal::Rotations
+                && tree.len() == n
+                && best.is_none_or(|(b, _, _)| value > b)
+            {
+                best = Some((value, i, Some(step)));
+            }
+
+            let key = tree.to_brackets();
+            if seen.insert(key) {
+                states.push(State {
+                    tree,
+                    parent: Some((i, step)),
+                });
+            }
+        }
+        i += 1;
+    }
+
+    let (_, mut state, last) = best.unwrap_or((0, 0, None));
+    let mut steps: Vec<Step> = last.into_iter().collect();
+    while let Some((parent, step)) = states[state].parent {
+        steps.push(step);
+        state = parent;
+    }
+    steps.reverse();
+    steps
+}
+
+fn heuristic(n: usize, options: &SearchOptions) -> Vec<Step> {
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let restarts = 4;
+
+    let score = |steps: &[Step]| {
+        let (value, tie_break, _) = play(steps, options);
+        (value, tie_break)
+    };
+
+    let mut best: Option<((usize, usize), Vec<Step>)> = None;
+    for _ in 0..restarts {
+        let mut current = random_steps(n, options.extra, &mut rng);
+        let mut value = score(&current);
+
+        for _ in 0..options.iterations / restarts {
+            let mut next = current.clone();
+            let i = rng.gen_range(0..next.len());
+            let j = rng.gen_range(0..next.len());
+            next.swap(i, j);
+            if !removes_after_adds(&next) {
+                continue;
+            }
+
+            // equal values are taken to move along plateaus
+            let next_value = score(&next);
+            if next_value >= value {
+                current = next;
+                value = next_value;
+            }
+        }
+
+        if best.as_ref().is_none_or(|(b, _)| value > *b) {
+            best = Some((value, current));
+        }
+    }
+    best.map(|(_, steps)| steps).unwrap_or_default()
+}
+
+// every value from 0..n+extra is added once, `extra` of them are removed later
+fn random_steps(n: usize, extra: usize, rng: &mut StdRng) -> Vec<Step> {
+    let mut values: Vec<i32> = (0..(n + extra) as i32).collect();
+    values.shuffle(rng);
+
+    let mut steps: Vec<Step> = values.iter().map(|&v| Step::Add(v)).collect();
+    for &v in values.choose_multiple(rng, extra) {
+        let added = steps.iter().position(|&s| s == Step::Add(v)).unwrap();
+        let at = rng.gen_range(added + 1..=steps.len());
+        steps.insert(at, Step::Remove(v));
+    }
+    steps
+}
+
+fn removes_after_adds(steps: &[Step]) -> bool {
+    let mut added = Vec::new();
+    steps.iter().all(|step| match step {
+        Step::Add(v) => {
+            added.push(*v);
+            true
+        }
+        Step::Remove(v) => added.contains(v),
+    })
+}
@@ -0,0 +1,304 @@
+use std::{
+    collections::BTreeMap,
+    env,
+    fmt::Write as _,
+    panic::{self, AssertUnwindSafe},
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{RbTree, Strategy};
+
+// set it to rerun a failed case, e.g. `RBTREE_SEED=42 cargo test stress`
+pub(crate) const SEED_VAR: &str = "RBTREE_SEED";
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum Op {
+    Add(i32),
+    Remove(i32),
+    Contains(i32),
+}
+
+// relative weights of operations
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Mix {
+    // a random value which may be in the tree already
+    pub add: u32,
+    // a value which is definitely in the tree
+    pub duplicate: u32,
+    pub remove: u32,
+    // a value which is definitely not in the tree
+    pub remove_missing: u32,
+    pub contains: u32,
+}
+
+impl Default for Mix {
+    fn default() -> Self {
+        Mix {
+            add: 6,
+            duplicate: 1,
+            remove: 4,
+            remove_missing: 1,
+            contains: 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Config {
+    pub seed: u64,
+    pub steps: usize,
+    // values are taken from 0..max
+    pub max: i32,
+    pub mix: Mix,
+    pub strategy: Strategy,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            seed: 0,
+            steps: 2000,
+            max: 500,
+            mix: Mix::default(),
+            strategy: Strategy::BottomUp,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Failure {
+    pub seed: u64,
+    pub strategy: Strategy,
+    // the shortest sequence found which still fails
+    pub ops: Vec<Op>,
+    pub message: String,
+}
+
+impl Failure {
+    // the failed case as a test to paste into `tests.rs`
+    pub fn reproducer(&self) -> String {
+        let mut model = Model::default();
+        let mut out = String::new();
+
+        let _ = writeln!(out, "#[test]");
+        let _ = writeln!(out, "fn test_stress_seed_{}() {{", self.seed);
+        let _ = writeln!(out, "    // {}", self.message);
+        let _ = match self.strategy {
+            Strategy::BottomUp => writeln!(out, "    let mut t = RbTree::<i32>::new();"),
+            strategy => writeln!(
+                out,
+                "    let mut t = RbTree::<i32>::with_strategy(Strategy::{:?});",
+                strategy
+            ),
+        };
+        for &op in &self.ops {
+            let _ = match op {
+                Op::Add(v) => writeln!(out, "    t.add({});", v),
+                Op::Remove(v) if model.contains(v) => {
+                    writeln!(out, "    assert!(t.remove(&{}));", v)
+                }
+                Op::Remove(v) => writeln!(out, "    assert!(!t.remove(&{}));", v),
+                Op::Contains(v) if model.contains(v) => {
+                    writeln!(out, "    assert!(t.contains(&{}));", v)
+                }
+                Op::Contains(v) => writeln!(out, "    assert!(!t.contains(&{}));", v),
+            };
+            model.apply(op);
+        }
+        let _ = writeln!(out, "    assert_eq!(t.len(), {});", model.len);
+        let _ = writeln!(out, "    assert!(t.validate().is_ok());");
+        let _ = writeln!(out, "}}");
+        out
+    }
+}
+
+// seed from the environment or the given one
+pub(crate) fn seed_or(seed: u64) -> u64 {
+    env::var(SEED_VAR)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(seed)
+}
+
+pub(crate) fn generate(config: &Config) -> Vec<Op> {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut model = Model::default();
+    let mix = config.mix;
+    let total = mix.add + mix.duplicate + mix.remove + mix.remove_missing + mix.contains;
+    assert!(total > 0, "at least one operation should have a weight");
+
+    let mut ops = Vec::with_capacity(config.steps);
+    for _ in 0..config.steps {
+        let mut pick = rng.gen_range(0..total);
+        let mut next = |weight: u32| {
+            let hit = pick < weight;
+            pick = pick.wrapping_sub(weight);
+            hit
+        };
+
+        let op = if next(mix.add) {
+            Op::Add(rng.gen_range(0..config.max))
+        } else if next(mix.duplicate) {
+            // falls back to a random value while the tree is empty
+            Op::Add(model.pick(&mut rng).unwrap_or(rng.gen_range(0..config.max)))
+        } else if next(mix.remove) {
+            Op::Remove(model.pick(&mut rng).unwrap_or(rng.gen_range(0..config.max)))
+        } else if next(mix.remove_missing) {
+            // values out of 0..max are never added
+            Op::Remove(config.max + rng.gen_range(0..config.max.max(1)))
+        } else {
+            Op::Contains(rng.gen_range(0..config.max))
+        };
+
+        model.apply(op);
+        ops.push(op);
+    }
+    ops
+}
+
+// generates operations from the config, runs them and shrinks the failed sequence
+pub(crate) fn stress(config: &Config) -> Result<(), Failure> {
+    let ops = generate(config);
+    let message = match execute(&ops, config.strategy) {
+        Ok(()) => return Ok(()),
+        Err(message) => message,
+    };
+
+    // a panicking tree prints a message on every try, the panic hook is global
+    // and is left alone not to hide panics of other tests
+    let ops = shrink(ops, |ops| execute(ops, config.strategy).is_err());
+
+    let message = execute(&ops, config.strategy).err().unwrap_or(message);
+    Err(Failure {
+        seed: config.seed,
+        strategy: config.strategy,
+        ops,
+        message,
+    })
+}
+
+// runs operations on the tree and on the model, compares them after every step
+pub(crate) fn execute(ops: &[Op], strategy: Strategy) -> Result<(), String> {
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut tree = RbTree::<i32>::with_strategy(strategy);
+        let mut model = Model::default();
+
+        for (i, &op) in ops.iter().enumerate() {
+            let (got, expected) = match op {
+                Op::Add(v) => {
+                    tree.add(v);
+                    (true, true)
+                }
+                Op::Remove(v) => (tree.remove(&v), model.contains(v)),
+                Op::Contains(v) => (tree.contains(&v), model.contains(v)),
+            };
+            model.apply(op);
+
+            if got != expected {
+                return Err(format!("step {}: {:?} returned {}", i, op, got));
+            }
+            if let Err(violations) = tree.validate() {
+                return Err(format!(
+                    "step {}: {:?} broke the tree: {}",
+                    i, op, violations[0]
+                ));
+            }
+            if tree.len() != model.len {
+                return Err(format!("step {}: {:?} len is {}", i, op, tree.len()));
+            }
+
+            let mut values = Vec::with_capacity(tree.len());
+            tree.for_each(|v| values.push(*v));
+            if !values.iter().copied().eq(model.values()) {
+                return Err(format!("step {}: {:?} values differ from the model", i, op));
+            }
+        }
+        Ok(())
+    }));
+
+    match result {
+        Ok(r) => r,
+        Err(e) => {
+            let text = e
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| e.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            Err(format!("panicked: {}", text))
+        }
+    }
+}
+
+// removes chunks of operations while the sequence still fails,
+// chunks get smaller until single operations are tried
+pub(crate) fn shrink<F: Fn(&[Op]) -> bool>(mut ops: Vec<Op>, fails: F) -> Vec<Op> {
+    let mut chunk = ops.len() / 2;
+
+    while chunk > 0 {
+        let mut start = 0;
+        let mut removed = false;
+
+        while start < ops.len() {
+            let end = (start + chunk).min(ops.len());
+            let candidate: Vec<Op> = ops[..start].iter().chain(&ops[end..]).copied().collect();
+
+            if fails(&candidate) {
+                ops = candidate;
+                removed = true;
+            } else {
+                start += chunk;
+            }
+        }
+        if !removed {
+            chunk /= 2;
+        }
+    }
+    ops
+}
+
+// the multiset, value -> number of copies
+#[derive(Default)]
+struct Model {
+    counts: BTreeMap<i32, usize>,
+    len: usize,
+}
+
+impl Model {
+    fn apply(&mut self, op: Op) {
+        match op {
+            Op::Add(v) => {
+                *self.counts.entry(v).or_default() += 1;
+                self.len += 1;
+            }
+            Op::Remove(v) => {
+                if let Some(n) = self.counts.get_mut(&v) {
+                    *n -= 1;
+                    if *n == 0 {
+                        self.counts.remove(&v);
+                    }
+                    self.len -= 1;
+                }
+            }
+            Op::Contains(_) => {}
+        }
+    }
+
+    fn contains(&self, v: i32) -> bool {
+        self.counts.contains_key(&v)
+    }
+
+    fn pick(&self, rng: &mut StdRng) -> Option<i32> {
+        if self.counts.is_empty() {
+            return None;
+        }
+        let i = rng.gen_range(0..self.counts.len());
+        self.counts.keys().nth(i).copied()
+    }
+
+    fn values(&self) -> impl Iterator<Item = i32> + '_ {
+        self.counts
+            .iter()
+            .flat_map(|(&v, &n)| std::iter::repeat_n(v, n))
+    }
+}
@@ -0,0 +1,301 @@
+use std::{fmt::Write as _, io};
+
+use super::{ancestor::Pos, node::*, RbTree};
+
+const MARGIN: f64 = 20.0;
+// vertical distance between levels
+const LEVEL: f64 = 60.0;
+// minimal horizontal gap between neighbour nodes
+const GAP: f64 = 12.0;
+const NODE_HEIGHT: f64 = 28.0;
+const CHAR_WIDTH: f64 = 8.5;
+
+// node laid out by tidy tree algorithm, nodes are kept in preorder
+// so every parent comes before its children
+struct Placed {
+    label: String,
+    color: Color,
+    depth: usize,
+    black_height: usize,
+    half_width: f64,
+    // offset from the parent, absolute position after `place`
+    x: f64,
+    parent: Option<usize>,
+    children: [Option<usize>; 2],
+}
+
+// horizontal extent of a subtree on every level relative to its root,
+// the deepest level goes first so the root level is pushed last
+struct Contour {
+    levels: Vec<(f64, f64)>,
+    // added to every level, shifting the whole contour is O(1)
+    offset: f64,
+}
+
+impl<T> RbTree<T>
+where
+    T: std::fmt::Debug + std::cmp::Ord + std::cmp::Eq + std::fmt::Display,
+{
+    // renders the tree as standalone SVG document, hover a node to see details
+    pub fn to_svg(&self) -> String {
+        let mut placed = self.root.as_ref().map(Self::layout).unwrap_or_default();
+
+        let mut width = 2.0 * MARGIN;
+        let mut height = 2.0 * MARGIN;
+        if !placed.is_empty() {
+            // shift the tree so the leftmost node touches the margin
+            let (min, max) = place(&mut placed);
+            for node in placed.iter_mut() {
+                node.x += MARGIN - min;
+            }
+            let depth = placed.iter().map(|node| node.depth).max().unwrap_or(0);
+
+            width += max - min;
+            height += NODE_HEIGHT + LEVEL * depth as f64;
+        }
+
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" \
+             font-family=\"Helvetica, Arial, sans-serif\" font-size=\"14\">",
+            w = width.ceil(),
+            h = height.ceil()
+        );
+        out += "<style>.node:hover rect { stroke: #ffb000; stroke-width: 4; }</style>\n";
+
+        if placed.is_empty() {
+            let _ = writeln!(
+                out,
+                "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">empty</text>",
+                width / 2.0,
+                height / 2.0
+            );
+        } else {
+            out += "<g stroke=\"#555\" stroke-width=\"2\">\n";
+            edges(&placed, &mut out);
+            out += "</g>\n";
+            for node in placed.iter() {
+                write_node(node, &mut out);
+            }
+        }
+        out += "</svg>\n";
+        out
+    }
+
+    // self-contained HTML page with the SVG inside
+    pub fn to_html(&self) -> String {
+        let mut out = String::new();
+        out += "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>RbTree</title>\n";
+        out += "<style>body { margin: 0; padding: 16px; overflow: auto; font-family: Helvetica, Arial, sans-serif; }</style>\n";
+        out += "</head>\n<body>\n";
+        let _ = writeln!(out, "<p>RbTree, {} nodes</p>", self.len);
+        out += &self.to_svg();
+        out += "</body>\n</html>\n";
+        out
+    }
+
+    pub fn write_svg<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(self.to_svg().as_bytes())
+    }
+
+    pub fn write_html<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(self.to_html().as_bytes())
+    }
+
+    // Reingold-Tilford algorithm, lays out subtrees independently and then
+    // pushes them apart until their contours don't overlap on every level
+    fn layout(root: &Node<T>) -> Vec<Placed> {
+        let mut placed: Vec<Placed> = Vec::new();
+
+        // uses explicit stack instead of recursion, (node, parent index, side)
+        let mut stack = vec![(root.clone(), None::<(usize, usize)>)];
+        while let Some((node, parent)) = stack.pop() {
+            let n = node.borrow();
+            let i = placed.len();
+            let label = n.val.to_string();
+            let half_width =
+                (label.chars().count() as f64 * CHAR_WIDTH + 16.0).max(NODE_HEIGHT) / 2.0;
+
+            let mut depth = 0;
+            if let Some((p, pos)) = parent {
+                placed[p].children[pos] = Some(i);
+                depth = placed[p].depth + 1;
+            }
+            placed.push(Placed {
+                label,
+                color: n.color,
+                depth,
+                black_height: 0,
+                half_width,
+                x: 0.0,
+                parent: parent.map(|(p, _)| p),
+                children: [None, None],
+            });
+
+            for pos in [Pos::RIGHT, Pos::LEFT] {
+                if let Some(child) = n.children[pos].as_ref() {
+                    stack.push((child.clone(), Some((i, pos))));
+                }
+            }
+        }
+
+        // walking backwards lays out both subtrees before their root
+        let mut contours: Vec<Option<Contour>> = placed.iter().map(|_| None).collect();
+        for i in (0..placed.len()).rev() {
+            let half_width = placed[i].half_width;
+            let mut black_height = 0;
+
+            let mut contour = match placed[i].children {
+                [Some(l), Some(r)] => {
+                    let l_contour = contours[l].take().unwrap();
+                    let r_contour = contours[r].take().unwrap();
+                    // the smallest distance between children roots which keeps the gap
+                    let mut distance = 0.0f64;
+                    for (l_level, r_level) in l_contour.top_down().zip(r_contour.top_down()) {
+                        distance = distance.max(l_level.1 - r_level.0 + GAP);
+                    }
+                    placed[l].x = -distance / 2.0;
+                    placed[r].x = distance / 2.0;
+
+                    black_height = placed[l].black_height;
+                    l_contour
+                        .shifted(placed[l].x)
+                        .merge(r_contour.shifted(placed[r].x))
+                }
+                [Some(l), None] => {
+                    // keep the only child on its side
+                    placed[l].x = -(half_width + GAP) / 2.0;
+                    black_height = placed[l].black_height;
+                    contours[l].take().unwrap().shifted(placed[l].x)
+                }
+                [None, Some(r)] => {
+                    placed[r].x = (half_width + GAP) / 2.0;
+                    contours[r].take().unwrap().shifted(placed[r].x)
+                }
+                [None, None] => Contour {
+                    levels: Vec::new(),
+                    offset: 0.0,
+                },
+            };
+            contour.push(-half_width, half_width);
+
+            if placed[i].color == Color::Black {
+                black_height += 1;
+            }
+            placed[i].black_height = black_height;
+            contours[i] = Some(contour);
+        }
+        placed
+    }
+}
+
+impl Contour {
+    fn top_down(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        self.levels
+            .iter()
+            .rev()
+            .map(|&(left, right)| (left + self.offset, right + self.offset))
+    }
+
+    fn shifted(mut self, dx: f64) -> Contour {
+        self.offset += dx;
+        self
+    }
+
+    // adds a level above the others
+    fn push(&mut self, left: f64, right: f64) {
+        self.levels.push((left - self.offset, right - self.offset));
+    }
+
+    // merges into the deeper contour, so a long path costs only its length once
+    fn merge(self, other: Contour) -> Contour {
+        let (mut deep, shallow) = match self.levels.len() >= other.levels.len() {
+            true => (self, other),
+            false => (other, self),
+        };
+        let top = deep.levels.len() - 1;
+        for (i, (left, right)) in shallow.top_down().enumerate() {
+            let level = &mut deep.levels[top - i];
+            level.0 = level.0.min(left - deep.offset);
+            level.1 = level.1.max(right - deep.offset);
+        }
+        deep
+    }
+}
+
+// turns relative offsets to absolute positions, returns the extent of the tree
+fn place(placed: &mut [Placed]) -> (f64, f64) {
+    let mut min = 0.0f64;
+    let mut max = 0.0f64;
+    for i in 0..placed.len() {
+        if let Some(p) = placed[i].parent {
+            placed[i].x += placed[p].x;
+        }
+        let node = &placed[i];
+        min = min.min(node.x - node.half_width);
+        max = max.max(node.x + node.half_width);
+    }
+    (min, max)
+}
+
+fn y(depth: usize) -> f64 {
+    MARGIN + NODE_HEIGHT / 2.0 + LEVEL * depth as f64
+}
+
+// in preorder, the same order the nodes are drawn
+fn edges(placed: &[Placed], out: &mut String) {
+    for child in placed.iter() {
+        let parent = match child.parent {
+            Some(p) => &placed[p],
+            None => continue,
+        };
+        let _ = writeln!(
+            out,
+            "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\"/>",
+            parent.x,
+            y(parent.depth),
+            child.x,
+            y(child.depth)
+        );
+    }
+}
+
+fn write_node(node: &Placed, out: &mut String) {
+    let fill = match node.color {
+        Color::Red => "#d03030",
+        Color::Black => "#202020",
+    };
+    let label = escape(&node.label);
+
+    out.push_str("<g class=\"node\">");
+    let _ = write!(
+        out,
+        "<title>value: {}&#10;black height: {}&#10;depth: {}</title>",
+        label, node.black_height, node.depth
+    );
+    let _ = write!(
+        out,
+        "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{}\" rx=\"{}\" fill=\"{}\"/>",
+        node.x - node.half_width,
+        y(node.depth) - NODE_HEIGHT / 2.0,
+        2.0 * node.half_width,
+        NODE_HEIGHT,
+        NODE_HEIGHT / 2.0,
+        fill
+    );
+    let _ = writeln!(
+        out,
+        "<text x=\"{:.1}\" y=\"{:.1}\" fill=\"white\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text></g>",
+        node.x,
+        y(node.depth),
+        label
+    );
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -1,33 +1,45 @@
 #[cfg(test)]
 mod test {
-    use crate::RbTree;
+    use crate::rb_tree::node::{Color, Node, RbTreeNode};
+    use crate::rb_tree::stress::{self, Config, Failure, Mix, Op};
+    use crate::{
+        side_by_side, worst_case, CostCounters, Costs, DecodeError, DiagramColors, DiagramOptions,
+        DotOptions, Encoding, Event, Found, Glyphs, Goal, LlrbTree, Node234, Observer, Operation,
+        ParseError, PrintOptions, RbTree, Rotation, SearchOptions, Side, Step, Strategy,
+        TikzOptions, Tree234, TreeStats, ValidTrees, Violation,
+    };
     use rand::seq::SliceRandom;
     use rand::Rng;
+    use std::{cell::RefCell, collections::HashSet, rc::Rc};
 
     const N: usize = 1000;
     const MAX: i32 = 10000;
     const PRINT_SEQ: bool = false;
+    // tests which change the tree run for all of them
+    const STRATEGIES: [Strategy; 2] = [Strategy::BottomUp, Strategy::TopDown];
 
     #[test]
     fn test_add() {
         let mut rng = rand::thread_rng();
 
-        let mut t = RbTree::<i32>::new();
+        for strategy in STRATEGIES {
+            let mut t = RbTree::<i32>::with_strategy(strategy);
 
-        for _ in 0..N {
-            let v = rng.gen_range(0..MAX);
-            if PRINT_SEQ {
-                println!("t.add({});", v);
-            }
-            t.add(v);
+            for _ in 0..N {
+                let v = rng.gen_range(0..MAX);
+                if PRINT_SEQ {
+                    println!("t.add({});", v);
+                }
+                t.add(v);
 
-            let valid = t.is_valid();
-            if PRINT_SEQ && !valid {
-                t.print();
+                let valid = t.is_valid();
+                if PRINT_SEQ && !valid {
+                    t.print();
+                }
+                assert!(valid);
             }
-            assert_eq!(valid, true);
+            assert_eq!(t.len(), N);
         }
-        assert_eq!(t.len(), N);
     }
 
     #[test]
@@ -39,32 +51,1697 @@ mod test {
             vec.push(rng.gen_range(0..MAX));
         }
 
+        for strategy in STRATEGIES {
+            let mut t = RbTree::<i32>::with_strategy(strategy);
+
+            for &v in vec.iter() {
+                if PRINT_SEQ {
+                    println!("t.add({});", v);
+                }
+                t.add(v);
+            }
+
+            vec.shuffle(&mut rng);
+
+            for &v in vec.iter() {
+                if PRINT_SEQ {
+                    println!("t.remove(&{});", v);
+                }
+                let ok = t.remove(&v);
+                assert!(ok);
+
+                let valid = t.is_valid();
+                if PRINT_SEQ && !valid {
+                    t.print();
+                }
+                assert!(valid);
+            }
+            assert_eq!(t.len(), 0);
+        }
+    }
+
+    #[test]
+    fn test_top_down() {
+        let mut t = RbTree::<i32>::with_strategy(Strategy::TopDown);
+        assert_eq!(t.strategy(), Strategy::TopDown);
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let sink = events.clone();
+        t.set_observer(Box::new(move |e: &Event<i32>| {
+            sink.borrow_mut().push(e.clone())
+        }));
+
+        for v in [1, 2, 3] {
+            t.add(v);
+        }
+        assert_eq!(t.root.as_ref().unwrap().borrow().val, 2);
+        assert!(events.borrow().contains(&Event::InsertCase(2)));
+
+        // the root with two RED children is split on the way down
+        events.borrow_mut().clear();
+        t.add(4);
+        assert_eq!(
+            events.borrow()[..3],
+            [
+                Event::Operation(Operation::Add),
+                Event::InsertCase(1),
+                Event::Recolor {
+                    node: 2,
+                    from: Color::Black,
+                    to: Color::Red
+                },
+            ]
+        );
+        assert!(t.is_valid());
+
+        // the node with the value gets the predecessor value
+        events.borrow_mut().clear();
+        assert!(t.remove(&2));
+        assert!(events.borrow().contains(&Event::SwapNodes { a: 2, b: 1 }));
+        assert_eq!(values(&t), vec![1, 3, 4]);
+        assert!(t.is_valid());
+
+        // a missing value changes colors but not values
+        assert!(!t.remove(&7));
+        assert_eq!(values(&t), vec![1, 3, 4]);
+        assert!(t.is_valid());
+
+        // duplicates are removed one by one
+        for _ in 0..3 {
+            t.add(3);
+        }
+        for left in (0..4).rev() {
+            assert!(t.remove(&3));
+            assert_eq!(values(&t).iter().filter(|&&v| v == 3).count(), left);
+            assert!(t.is_valid());
+        }
+        assert!(!t.remove(&3));
+
+        // every case runs and no path is kept
+        t.take_observer();
+        let counters = Rc::new(RefCell::new(CostCounters::default()));
+        t.set_observer(Box::new(counters.clone()));
+        let ops = stress::generate(&Config {
+            seed: 3,
+            steps: 5000,
+            max: 1000,
+            ..Default::default()
+        });
+        for (i, op) in ops.into_iter().enumerate() {
+            // both strategies work on the same tree
+            if i % 1000 == 0 {
+                let next = match t.strategy() {
+                    Strategy::BottomUp => Strategy::TopDown,
+                    Strategy::TopDown => Strategy::BottomUp,
+                };
+                t.set_strategy(next);
+                assert!(t.is_valid());
+            }
+            match op {
+                Op::Add(v) => t.add(v),
+                Op::Remove(v) => {
+                    t.remove(&v);
+                }
+                Op::Contains(_) => {}
+            }
+        }
+        assert!(t.is_valid());
+        assert_eq!(counters.borrow().snapshot().uncovered(), vec![]);
+
+        let mut t = RbTree::with_strategy(Strategy::TopDown);
+        for v in 0..100 {
+            t.add(v);
+        }
+        for v in 0..50 {
+            t.remove(&v);
+        }
+        assert_eq!(t.path.capacity(), 0);
+        assert_eq!(t.clone().strategy(), Strategy::TopDown);
+    }
+
+    fn values<T: Clone + std::fmt::Debug + Ord + std::fmt::Display>(t: &RbTree<T>) -> Vec<T> {
+        let mut vec = Vec::new();
+        t.for_each(|v| vec.push(v.clone()));
+        vec
+    }
+
+    #[test]
+    fn test_llrb() {
+        let mut t = LlrbTree::new();
+        let mut rb = RbTree::new();
+        for v in 1..=6 {
+            t.add(v);
+            rb.add(v);
+            assert!(t.validate().is_ok());
+        }
+        // the same values, RED nodes lean left only in LLRB
+        assert_eq!(
+            t.to_string(),
+            "└─4b\n  ├─6b\n  │ └─5r\n  └─2r\n    ├─3b\n    └─1b\n"
+        );
+        assert_eq!(
+            rb.to_string(),
+            "└─2b\n  ├─4r\n  │ ├─5b\n  │ │ ├─6r\n  │ └─3b\n  └─1b\n"
+        );
+        assert!(t.tree().validate_llrb().is_ok());
+        assert!(rb.validate().is_ok());
+        let violations = rb.validate_llrb().unwrap_err();
+        assert_eq!(
+            violations
+                .iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<String>>(),
+            vec![
+                "RED node is a right child, see val: 4 at root/R",
+                "RED node is a right child, see val: 6 at root/R/R/R",
+            ]
+        );
+
+        // the sorted vector is the model
+        let mut model: Vec<i32> = (1..=6).collect();
+        let ops = stress::generate(&Config {
+            seed: 5,
+            steps: 3000,
+            max: 300,
+            ..Default::default()
+        });
+        for op in ops {
+            match op {
+                Op::Add(v) => {
+                    t.add(v);
+                    let i = model.partition_point(|&m| m <= v);
+                    model.insert(i, v);
+                }
+                Op::Remove(v) => {
+                    let found = model.binary_search(&v);
+                    assert_eq!(t.remove(&v), found.is_ok());
+                    if let Ok(i) = found {
+                        model.remove(i);
+                    }
+                }
+                Op::Contains(v) => assert_eq!(t.contains(&v), model.binary_search(&v).is_ok()),
+            }
+            assert!(t.validate().is_ok());
+            assert_eq!(t.len(), model.len());
+        }
+        let mut vals = Vec::new();
+        t.for_each(|v| vals.push(*v));
+        assert_eq!(vals, model);
+
+        // rotations on the same input
+        let counters = || Rc::new(RefCell::new(CostCounters::default()));
+        let (llrb_costs, rb_costs) = (counters(), counters());
+        let mut t = LlrbTree::new();
+        let mut rb = RbTree::new();
+        t.set_observer(Box::new(llrb_costs.clone()));
+        rb.set_observer(Box::new(rb_costs.clone()));
+        for v in 1..=100 {
+            t.add(v);
+            rb.add(v);
+        }
+        for v in (1..=100).step_by(3) {
+            assert!(t.remove(&v));
+            assert!(rb.remove(&v));
+        }
+        let (llrb_costs, rb_costs) = (llrb_costs.borrow().snapshot(), rb_costs.borrow().snapshot());
+        assert_eq!(llrb_costs.removes, rb_costs.removes);
+        // LLRB rotates on the way down to delete, bottom-up only when fixing
+        assert!(llrb_costs.total_remove.rotations > rb_costs.total_remove.rotations);
+        assert!(t.validate().is_ok());
+        assert_eq!(t.tree().len(), 66);
+
+        t.clear();
+        assert!(t.is_empty());
+        assert!(!t.remove(&1));
+    }
+
+    #[test]
+    fn test_iter() {
+        let mut t = RbTree::<i32>::new();
+        assert_eq!(t.iter().count(), 0);
+
+        for v in [5, 3, 8, 3, 1, 9, 5, 5] {
+            t.add(v);
+        }
+        assert_eq!(t.iter().collect::<Vec<i32>>(), values(&t));
+        // duplicates are kept, bounds of every kind
+        assert_eq!(t.range(3..=5).collect::<Vec<i32>>(), vec![3, 3, 5, 5, 5]);
+        assert_eq!(t.range(4..8).collect::<Vec<i32>>(), vec![5, 5, 5]);
+        assert_eq!(t.range(..3).collect::<Vec<i32>>(), vec![1]);
+        assert_eq!(t.range(8..).collect::<Vec<i32>>(), vec![8, 9]);
+        assert_eq!(t.range(10..).count(), 0);
+
+        // a long range on a bigger tree
+        for v in 10..1000 {
+            t.add(v);
+        }
+        assert!(t.range(100..900).eq(100..900));
+    }
+
+    #[test]
+    fn test_contains() {
         let mut t = RbTree::<i32>::new();
+        assert!(!t.contains(&1));
+
+        for v in [5, 3, 8, 3, 1] {
+            t.add(v);
+        }
+        assert!([1, 3, 5, 8].iter().all(|v| t.contains(v)));
+        assert!(![0, 2, 4, 9].iter().any(|v| t.contains(v)));
+
+        // one of duplicates is still there
+        t.remove(&3);
+        assert!(t.contains(&3));
+        t.remove(&3);
+        assert!(!t.contains(&3));
+    }
 
-        for i in 0..N {
-            let v = vec[i];
-            if PRINT_SEQ {
-                println!("t.add({});", v);
+    #[test]
+    fn test_stress() {
+        let mixes = [
+            Mix::default(),
+            // lots of equal values
+            Mix {
+                duplicate: 6,
+                ..Default::default()
+            },
+            // the tree stays small and is emptied often
+            Mix {
+                add: 3,
+                remove: 5,
+                remove_missing: 3,
+                ..Default::default()
+            },
+        ];
+
+        for strategy in STRATEGIES {
+            for (i, mix) in mixes.into_iter().enumerate() {
+                let config = Config {
+                    seed: stress::seed_or(i as u64),
+                    mix,
+                    strategy,
+                    ..Default::default()
+                };
+                if let Err(failure) = stress::stress(&config) {
+                    panic!(
+                        "seed {} failed with {:?}: {}\n{}",
+                        failure.seed,
+                        strategy,
+                        failure.message,
+                        failure.reproducer()
+                    );
+                }
             }
-            t.add(vec[i]);
+        }
+    }
+
+    #[test]
+    fn test_stress_shrink() {
+        // the same seed gives the same operations
+        let config = Config {
+            seed: 7,
+            steps: 100,
+            ..Default::default()
+        };
+        assert_eq!(stress::generate(&config), stress::generate(&config));
+        for strategy in STRATEGIES {
+            assert!(stress::execute(&stress::generate(&config), strategy).is_ok());
         }
 
-        vec.shuffle(&mut rng);
+        // pretends removing 5 after adding 5 and 7 is broken
+        let ops: Vec<Op> = [1, 5, 9, 7, 3, 5, 2]
+            .map(Op::Add)
+            .into_iter()
+            .chain([Op::Contains(5), Op::Remove(2), Op::Remove(5), Op::Remove(9)])
+            .collect();
+        let fails = |ops: &[Op]| {
+            let at = |op| ops.iter().position(|&o| o == op);
+            matches!((at(Op::Add(5)), at(Op::Add(7)), at(Op::Remove(5))),
+                (Some(a), Some(b), Some(r)) if a < r && b < r)
+        };
+        let shrunk = stress::shrink(ops, fails);
+        assert_eq!(shrunk, vec![Op::Add(7), Op::Add(5), Op::Remove(5)]);
 
-        for i in 0..N {
-            let v = vec[i];
-            if PRINT_SEQ {
-                println!("t.remove(&{});", v);
+        let mut failure = Failure {
+            seed: 7,
+            strategy: Strategy::BottomUp,
+            ops: vec![Op::Add(5), Op::Remove(5), Op::Remove(5), Op::Contains(1)],
+            message: "step 1: Remove(5) broke the tree".to_string(),
+        };
+        assert_eq!(
+            failure.reproducer(),
+            "#[test]
+fn test_stress_seed_7() {
+    // step 1: Remove(5) broke the tree
+    let mut t = RbTree::<i32>::new();
+    t.add(5);
+    assert!(t.remove(&5));
+    assert!(!t.remove(&5));
+    assert!(!t.contains(&1));
+    assert_eq!(t.len(), 0);
+    assert!(t.validate().is_ok());
+}
+"
+        );
+
+        failure.strategy = Strategy::TopDown;
+        assert!(failure
+            .reproducer()
+            .contains("let mut t = RbTree::<i32>::with_strategy(Strategy::TopDown);"));
+    }
+
+    #[test]
+    fn test_deep() {
+        // a chain is far deeper than any valid tree, walking it must not overflow the stack
+        let mut t = RbTree::<i32>::new();
+        let mut last = node(0, Color::Black, None, None);
+        t.root = Some(last.clone());
+        for v in 1..30_000 {
+            let next = node(v, Color::Black, None, None);
+            last.borrow_mut().children[1] = Some(next.clone());
+            last = next;
+        }
+        t.len = 30_000;
+
+        let violations = t.validate().unwrap_err();
+        // every node but the last one has different black heights, and the height is over the bound
+        assert_eq!(violations.len(), 30_000);
+        assert!(matches!(
+            violations.last(),
+            Some(Violation::HeightBoundExceeded { height: 30_000, .. })
+        ));
+
+        let zoomed = t.display_with(PrintOptions {
+            zoom: Some((&29_999, 1)),
+            ..Default::default()
+        });
+        assert_eq!(zoomed.to_string(), "└─29998b\n  ├─29999b ◀\n");
+
+        let explanation = t.explain().unwrap();
+        assert!(explanation.ends_with("... and 29992 more violations\n"));
+        // next to NIL on the left, the right subtree of 2 nodes can be RED and BLACK, 3 are too many
+        assert_eq!(t.find_coloring().unwrap_err().value, 29_997);
+
+        let brackets = t.to_brackets();
+        assert!(brackets.starts_with("(0b _ (1b _ (2b"));
+        let back = RbTree::<i32>::from_brackets(&brackets).unwrap();
+        assert_eq!(back.len(), 30_000);
+        assert_eq!(back.height(), 30_000);
+        let copy = back.clone();
+        assert_eq!(copy.to_brackets(), brackets);
+
+        // renderers walk the chain with explicit stacks too
+        assert_eq!(t.to_dot().matches(" -> ").count(), 2 * 29_999);
+        assert_eq!(t.to_html().matches("<line ").count(), 29_999);
+        // nested lines are indented by depth, a shorter chain keeps the output small
+        let tikz = t.subtree_to_tikz(&18_000, &TikzOptions::default()).unwrap();
+        assert_eq!(tikz.matches("phantom").count(), 11_999);
+
+        // paths to NIL below a node deeper than 32k are over 65535 chars long
+        let depth = 33_000;
+        let brackets = (0..depth)
+            .map(|v| format!("({}b _ ", v))
+            .collect::<String>()
+            + &format!("{}b", depth)
+            + &")".repeat(depth);
+        let deeper = RbTree::<i32>::from_brackets(&brackets).unwrap();
+        let mismatch = Violation::BlackHeightMismatch {
+            value: depth as i32 - 1,
+            path: vec![Side::Right; depth - 1],
+            left: 0,
+            right: 1,
+        };
+        let mut out = String::new();
+        deeper
+            .explain_violations(&mut out, &[mismatch], &PrintOptions::default())
+            .unwrap();
+        assert!(out.contains(&format!("/R/L    {}\n", depth)));
+        assert!(out.ends_with(&format!("/R/R  {}\n", depth + 1)));
+
+        t.clear();
+        // the path buffer is kept between operations but holds no nodes
+        for v in 0..100 {
+            t.add(v);
+        }
+        t.remove(&50);
+        assert!(t.path.is_empty());
+        assert!(t.path.capacity() > 0);
+    }
+
+    #[test]
+    fn test_drop() {
+        // skewed to both sides, the default drop would overflow the stack
+        let mut t = RbTree::<i32>::new();
+        let mut last = node(0, Color::Black, None, None);
+        t.root = Some(last.clone());
+        for v in 1..1_000_000 {
+            let next = node(v, Color::Black, None, None);
+            last.borrow_mut().children[(v % 3 == 0) as usize] = Some(next.clone());
+            last = next;
+        }
+        drop(last);
+        drop(t);
+
+        let mut t = RbTree::<i32>::new();
+        for v in 0..1000 {
+            t.add(v);
+        }
+        // the subtree somebody holds survives
+        let kept = t.root.as_ref().unwrap().borrow().children[0]
+            .clone()
+            .unwrap();
+        let kept_len = count(Some(&kept));
+
+        t.clear();
+        assert!(t.is_empty());
+        assert!(t.root.is_none());
+        assert_eq!(count(Some(&kept)), kept_len);
+        assert_eq!(Rc::strong_count(&kept), 1);
+
+        // the tree is usable after clear
+        t.add(1);
+        assert_eq!(t.len(), 1);
+        assert!(t.validate().is_ok());
+    }
+
+    #[test]
+    fn test_serialize() {
+        let mut rng = rand::thread_rng();
+
+        for n in [0, 1, 2, 3, 7, 8, 100, N] {
+            let mut t = RbTree::<i32>::new();
+            for _ in 0..n {
+                t.add(rng.gen_range(-MAX..MAX));
             }
-            let ok = t.remove(&v);
-            assert_eq!(ok, true);
 
-            let valid = t.is_valid();
-            if PRINT_SEQ && !valid {
-                t.print();
+            for encoding in [Encoding::Plain, Encoding::Delta] {
+                let bytes = t.to_bytes(encoding);
+                let mut loaded = RbTree::<i32>::from_bytes(&bytes).unwrap();
+
+                assert!(loaded.is_valid());
+                assert_eq!(loaded.len(), n);
+                assert_eq!(values(&loaded), values(&t));
+
+                // the loaded tree keeps working as usual
+                loaded.add(MAX);
+                assert!(loaded.remove(&MAX));
+                assert!(loaded.is_valid());
             }
-            assert_eq!(valid, true);
         }
-        assert_eq!(t.len(), 0);
+
+        let mut t = RbTree::<String>::new();
+        for s in ["pear", "apple", "", "plum", "apple"] {
+            t.add(s.to_string());
+        }
+        let loaded = RbTree::<String>::from_bytes(&t.to_bytes(Encoding::Delta)).unwrap();
+        assert_eq!(values(&loaded), values(&t));
+    }
+
+    #[test]
+    fn test_deserialize_errors() {
+        let mut t = RbTree::<u16>::new();
+        for v in [5, 3, 9, 1] {
+            t.add(v);
+        }
+        let bytes = t.to_bytes(Encoding::Plain);
+
+        let mut corrupted = bytes.clone();
+        corrupted[16] ^= 0xff;
+        assert!(matches!(
+            RbTree::<u16>::from_bytes(&corrupted),
+            Err(DecodeError::ChecksumMismatch { .. })
+        ));
+
+        assert!(matches!(
+            RbTree::<u16>::from_bytes(&bytes[..10]),
+            Err(DecodeError::Truncated)
+        ));
+        assert!(matches!(
+            RbTree::<u16>::from_bytes(b"nope"),
+            Err(DecodeError::BadMagic)
+        ));
+        assert!(matches!(
+            RbTree::<u32>::from_bytes(&bytes),
+            Err(DecodeError::WidthMismatch { .. })
+        ));
+
+        // swap two entries and fix the checksum, the data is well formed but unsorted
+        let mut unsorted = RbTree::<u16>::new().to_bytes(Encoding::Plain);
+        unsorted.truncate(unsorted.len() - 4);
+        unsorted[7] = 2;
+        unsorted.extend_from_slice(&[9, 0, 5, 0]);
+        let checksum = crate::rb_tree::codec::crc32(&unsorted);
+        unsorted.extend_from_slice(&checksum.to_le_bytes());
+        assert!(matches!(
+            RbTree::<u16>::from_bytes(&unsorted),
+            Err(DecodeError::Unsorted { index: 1 })
+        ));
+
+        // the length of the second string takes more than 64 bits
+        let mut overflow = RbTree::<String>::new().to_bytes(Encoding::Plain);
+        overflow.truncate(overflow.len() - 4);
+        overflow[7] = 2;
+        overflow.extend_from_slice(&[1, b'a']);
+        overflow.extend_from_slice(&[0xff; 10]);
+        overflow.push(1);
+        let checksum = crate::rb_tree::codec::crc32(&overflow);
+        overflow.extend_from_slice(&checksum.to_le_bytes());
+        assert!(matches!(
+            RbTree::<String>::from_bytes(&overflow),
+            Err(DecodeError::InvalidValue { index: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_dot() {
+        let mut t = RbTree::<i32>::new();
+        for v in [5, 3, 8, 1] {
+            t.add(v);
+        }
+        assert_eq!(t.to_dot().matches(" -> ").count(), 4);
+
+        let dot = t.to_dot_with(&DotOptions {
+            nil_leaves: true,
+            search_path: Some(&1),
+            subtree: None,
+        });
+        assert!(dot.starts_with("digraph RbTree {"));
+        assert!(dot.contains("[label=\"5\", fillcolor=black, color="));
+        assert!(dot.contains("[label=\"1\", fillcolor=red, color="));
+        assert!(!dot.contains("[label=\"8\", fillcolor=black, color="));
+        // 4 nodes have 5 NIL leaves
+        assert_eq!(dot.matches("label=\"NIL\"").count(), 5);
+        assert_eq!(dot.matches("penwidth=3").count(), 2);
+
+        let mut buf = Vec::new();
+        t.write_dot(&mut buf).unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), t.to_dot());
+    }
+
+    #[test]
+    fn test_svg() {
+        let mut rng = rand::thread_rng();
+        let mut t = RbTree::<i32>::new();
+        for _ in 0..200 {
+            t.add(rng.gen_range(0..MAX));
+        }
+
+        let svg = t.to_svg();
+        assert!(svg.starts_with("<svg "));
+        assert_eq!(svg.matches("<rect ").count(), t.len());
+        assert_eq!(svg.matches("<line ").count(), t.len() - 1);
+        assert!(svg.contains("&#10;depth: 0</title>"));
+
+        // nodes on the same level never overlap
+        let attr = |rect: &str, name: &str| -> f64 {
+            let start = rect.find(&format!(" {}=\"", name)).unwrap() + name.len() + 3;
+            let len = rect[start..].find('"').unwrap();
+            rect[start..start + len].parse().unwrap()
+        };
+        let mut rects: Vec<(f64, f64, f64)> = svg
+            .split("<rect")
+            .skip(1)
+            .map(|r| (attr(r, "y"), attr(r, "x"), attr(r, "width")))
+            .collect();
+        rects.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        for pair in rects.windows(2) {
+            if pair[0].0 == pair[1].0 {
+                assert!(pair[0].1 + pair[0].2 <= pair[1].1);
+            }
+        }
+
+        assert!(t.to_html().contains(&svg));
+        assert!(RbTree::<i32>::new().to_svg().contains("empty"));
+    }
+
+    #[test]
+    fn test_tikz() {
+        let mut t = RbTree::<i32>::new();
+        for v in [5, 3, 8, 1] {
+            t.add(v);
+        }
+
+        let tikz = t.to_tikz();
+        assert!(tikz.contains("\\begin{forest}"));
+        assert!(tikz.contains("  [{5}, rb black\n"));
+        assert!(tikz.contains("      [{1}, rb red\n"));
+        // 3 has only left child, the right one keeps the place
+        assert_eq!(tikz.matches("[, phantom]").count(), 1);
+
+        let options = TikzOptions {
+            nil_leaves: true,
+            black_height: true,
+        };
+        let tikz = t.to_tikz_with(&options);
+        assert_eq!(tikz.matches("[, rb nil]").count(), 5);
+        assert!(tikz.contains("[{5}, rb black, label={[font=\\scriptsize]right:2}"));
+
+        let sub = t.subtree_to_tikz(&3, &options).unwrap();
+        assert!(sub.contains("  [{3}, rb black, label={[font=\\scriptsize]right:1}"));
+        assert!(!sub.contains("{5}"));
+        assert!(t.subtree_to_tikz(&4, &options).is_none());
+    }
+
+    #[test]
+    fn test_render() {
+        let mut t = RbTree::<i32>::new();
+        for v in [5, 3, 8, 1] {
+            t.add(v);
+        }
+        assert_eq!(t.to_string(), "└─5b\n  ├─8b\n  └─3b\n    └─1r\n");
+
+        let format = |v: &i32| format!("<{}>", v);
+        let mut out = String::new();
+        t.render(
+            &mut out,
+            &PrintOptions {
+                glyphs: Glyphs::Ascii,
+                ansi: true,
+                max_depth: Some(1),
+                format: Some(&format),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(out, "`-<5>\n  |-<8>\n  `-<3>\n    `-...\n");
+
+        let zoomed = t.display_with(PrintOptions {
+            zoom: Some((&3, 0)),
+            ..Default::default()
+        });
+        assert_eq!(zoomed.to_string(), "└─3b ◀\n  └─…\n");
+
+        let mut buf = Vec::new();
+        t.render_io(&mut buf, &PrintOptions::default()).unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), t.to_string());
+    }
+
+    #[test]
+    fn test_parse() {
+        let t: RbTree<i32> = "(5b (3r 1b 4b) (7b))".parse().unwrap();
+        assert_eq!(t.len(), 5);
+        assert!(t.is_valid());
+        let printed = "└─5b\n  ├─7b\n  └─3r\n    ├─4b\n    └─1b\n";
+        assert_eq!(t.to_string(), printed);
+        assert_eq!(t.to_brackets(), "(5b (3r 1b 4b) 7b)");
+
+        // printed forms of every kind give the same tree back
+        let ascii = t.display_with(PrintOptions {
+            glyphs: Glyphs::Ascii,
+            highlight: Some(&|v: &i32| *v == 3),
+            ..Default::default()
+        });
+        for s in [printed.to_string(), ascii.to_string()] {
+            let back: RbTree<i32> = s.parse().unwrap();
+            assert_eq!(back.to_string(), printed);
+            assert_eq!(back.len(), 5);
+        }
+
+        let mut rng = rand::thread_rng();
+        let mut t = RbTree::new();
+        for _ in 0..200 {
+            t.add(rng.gen_range(-50..50));
+        }
+        let back = RbTree::<i32>::from_printed(&t.to_string()).unwrap();
+        assert_eq!(back.to_string(), t.to_string());
+        let back = RbTree::<i32>::from_brackets(&t.to_brackets()).unwrap();
+        assert_eq!(back.to_brackets(), t.to_brackets());
+        assert_eq!(back.len(), t.len());
+
+        // values are written with Display, strings aren't quoted
+        let mut t = RbTree::new();
+        for s in ["pear", "fig", "kiwi", "b", "apple"] {
+            t.add(s.to_string());
+        }
+        assert_eq!(t.to_brackets(), "(kiwib (bb appler figr) pearb)");
+        let back = RbTree::<String>::from_brackets(&t.to_brackets()).unwrap();
+        assert_eq!(back.to_brackets(), t.to_brackets());
+        assert!(back.contains(&"b".to_string()));
+
+        // the shape is kept as it is, even a broken one
+        let mut t: RbTree<i32> = "(5r _ (7r 6b))".parse().unwrap();
+        assert_eq!(t.to_string(), "└─5r\n  ├─7r\n  │ └─6b\n");
+        let violations = t.validate().unwrap_err();
+        assert!(violations.contains(&Violation::RedRoot {
+            value: 5,
+            path: vec![]
+        }));
+        assert!(t.contains(&6));
+        t.clear();
+        assert_eq!(t.to_brackets(), "_");
+        for s in ["", "_", "\n"] {
+            assert!(s.parse::<RbTree<i32>>().unwrap().is_empty());
+        }
+
+        let err = |s: &str| s.parse::<RbTree<i32>>().unwrap_err();
+        assert_eq!(
+            err("└─5b\n    └─3b\n"),
+            ParseError::MissingParent { line: 2 }
+        );
+        assert_eq!(
+            err("└─5b\n  └─3b\n  └─1b\n"),
+            ParseError::SlotTaken { line: 3 }
+        );
+        assert_eq!(err("└─5b\n└─3b\n"), ParseError::SlotTaken { line: 2 });
+        assert_eq!(err("└─5b\n  * 3b\n"), ParseError::BadLine { line: 2 });
+        assert_eq!(err("└─5b\n  └─…\n"), ParseError::Truncated { line: 2 });
+        assert_eq!(
+            err("(5x)"),
+            ParseError::BadNode {
+                token: "5x".to_string()
+            }
+        );
+        assert_eq!(
+            err("(b)").to_string(),
+            "invalid node \"b\", expected a value with b or r"
+        );
+        assert_eq!(
+            err("(5b 3b 7b 9b)"),
+            ParseError::TooManyChildren { offset: 10 }
+        );
+        assert_eq!(err("(5b (3b)"), ParseError::UnexpectedEnd);
+        assert_eq!(
+            err("(5b) 7b"),
+            ParseError::Unexpected {
+                offset: 5,
+                token: "7b".to_string()
+            }
+        );
+        assert_eq!(
+            err("(_)"),
+            ParseError::Unexpected {
+                offset: 1,
+                token: "_".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_diagram() {
+        let mut t = RbTree::<i32>::new();
+        for v in [5, 3, 8, 1] {
+            t.add(v);
+        }
+        let before = t.diagram();
+        assert_eq!(
+            before,
+            concat!(
+                "     _[5]_\n",
+                "    /     \\\n",
+                "  _[3]   [8]\n",
+                " /\n",
+                "(1)\n"
+            )
+        );
+
+        t.add(0);
+        assert_eq!(
+            side_by_side(&before, &t.diagram(), 4),
+            concat!(
+                "     _[5]_           ____[5]_\n",
+                "    /     \\         /        \\\n",
+                "  _[3]   [8]      _[1]_     [8]\n",
+                " /               /     \\\n",
+                "(1)             (0)   (3)\n"
+            )
+        );
+
+        // blank rows are kept
+        assert_eq!(side_by_side("a\n\nb\n", "x\n\ny\n", 2), "a  x\n\nb  y\n");
+
+        let ansi = t.diagram_with(&DiagramOptions {
+            colors: DiagramColors::Ansi,
+            format: None,
+        });
+        assert!(ansi.contains("\x1b[31m3\x1b[0m"));
+
+        let mut t = RbTree::<i32>::new();
+        for v in [5, 3, 8] {
+            t.add(v);
+        }
+        let blank = t.diagram_with(&DiagramOptions {
+            colors: DiagramColors::Ansi,
+            format: Some(&|_| String::new()),
+        });
+        assert_eq!(blank, "\n/ \\\n\x1b[31m \x1b[0m \x1b[31m \x1b[0m\n");
+        assert_eq!(RbTree::<i32>::new().diagram(), "");
+    }
+
+    fn node(
+        val: i32,
+        color: Color,
+        left: Option<Node<i32>>,
+        right: Option<Node<i32>>,
+    ) -> Node<i32> {
+        Rc::new(RefCell::new(RbTreeNode {
+            val,
+            color,
+            children: [left, right],
+        }))
+    }
+
+    fn tree(root: Node<i32>, len: usize) -> RbTree<i32> {
+        let mut t = RbTree::new();
+        t.root = Some(root);
+        t.len = len;
+        t
+    }
+
+    #[test]
+    fn test_validate() {
+        use Color::*;
+
+        let mut t = RbTree::<i32>::new();
+        let sizes = |s: TreeStats| (s.len, s.height, s.black_height);
+        assert_eq!(t.validate().map(sizes), Ok((0, 0, 0)));
+        for v in [5, 3, 8, 1] {
+            t.add(v);
+        }
+        assert_eq!(t.validate().map(sizes), Ok((4, 3, 2)));
+
+        let t = tree(node(5, Red, None, None), 1);
+        assert_eq!(
+            t.validate(),
+            Err(vec![Violation::RedRoot {
+                value: 5,
+                path: vec![]
+            }])
+        );
+
+        let t = tree(
+            node(
+                5,
+                Black,
+                Some(node(3, Red, Some(node(1, Red, None, None)), None)),
+                None,
+            ),
+            3,
+        );
+        assert_eq!(
+            t.validate(),
+            Err(vec![Violation::RedRed {
+                value: 1,
+                path: vec![Side::Left, Side::Left]
+            }])
+        );
+
+        // the node with only one child is checked as well
+        let t = tree(node(5, Black, Some(node(3, Black, None, None)), None), 2);
+        assert_eq!(
+            t.validate(),
+            Err(vec![Violation::BlackHeightMismatch {
+                value: 5,
+                path: vec![],
+                left: 1,
+                right: 0
+            }])
+        );
+
+        let t = tree(
+            node(
+                5,
+                Black,
+                Some(node(3, Red, None, Some(node(6, Black, None, None)))),
+                None,
+            ),
+            3,
+        );
+        let violations = t.validate().unwrap_err();
+        assert!(violations.contains(&Violation::OrderViolation {
+            value: 6,
+            path: vec![Side::Left, Side::Right]
+        }));
+
+        let t = tree(node(5, Black, None, None), 2);
+        assert_eq!(
+            t.validate(),
+            Err(vec![Violation::LenMismatch { len: 2, counted: 1 }])
+        );
+
+        // 6 nodes in a row, 2*log2(7) < 6
+        let mut chain = node(6, Black, None, None);
+        for v in (1..6).rev() {
+            chain = node(v, Black, None, Some(chain));
+        }
+        let violations = tree(chain, 6).validate().unwrap_err();
+        assert_eq!(
+            violations.last(),
+            Some(&Violation::HeightBoundExceeded {
+                value: 6,
+                path: vec![Side::Right; 5],
+                height: 6
+            })
+        );
+        assert_eq!(
+            violations.last().unwrap().to_string(),
+            "Height 6 is over 2*log2(n+1), see val: 6 at root/R/R/R/R/R"
+        );
+    }
+
+    // values which can't be cloned work with everything but `validate`,
+    // `explain` and observers
+    #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+    struct Id(u32);
+
+    impl std::fmt::Display for Id {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "#{}", self.0)
+        }
+    }
+
+    #[test]
+    fn test_without_clone() {
+        for strategy in STRATEGIES {
+            let mut t = RbTree::with_strategy(strategy);
+            for v in 0..100 {
+                t.add(Id(v));
+            }
+            for v in (0..100).step_by(2) {
+                assert!(t.remove(&Id(v)));
+            }
+            assert_eq!(t.len(), 50);
+            assert!(t.contains(&Id(7)));
+            assert!(!t.contains(&Id(8)));
+            assert!(t.is_valid());
+            assert!(t.stats().within_bound());
+            assert!(t.to_string().starts_with("└─Id("));
+
+            t.root.as_ref().unwrap().borrow_mut().color = Color::Red;
+            assert!(!t.is_valid());
+        }
+    }
+
+    fn count(node: Option<&Node<i32>>) -> usize {
+        node.map_or(0, |n| {
+            let r = n.borrow();
+            1 + count(r.children[0].as_ref()) + count(r.children[1].as_ref())
+        })
+    }
+
+    #[test]
+    fn test_explain() {
+        let mut t = RbTree::new();
+        for v in [5, 3, 8, 1] {
+            t.add(v);
+        }
+        assert_eq!(t.explain(), None);
+
+        let t: RbTree<i32> = "(5b (3b 1b 4b) 7b)".parse().unwrap();
+        assert_eq!(
+            t.explain().unwrap(),
+            "Different black heights, see val: 5 at root, left: 2 right: 1\n\
+             rule: numbers of BLACK levels in left and right subtrees are the same, \
+             so every path from a node down to a NIL has as many BLACK nodes\n\
+             └─5b ◀\n  ├─7b\n  └─3b\n    ├─4b\n    └─1b\n\
+             BLACK nodes on paths from the root to NIL below val: 5\n  \
+             root/L/L/L  3\n  root/L/L/R  3\n  root/L/R/L  3\n  root/L/R/R  3\n  \
+             root/R/L    2\n  root/R/R    2\n"
+        );
+
+        // the RED parent is marked too, violations are split by an empty line
+        let t: RbTree<i32> = "(5b (3r 1r) 7b)".parse().unwrap();
+        let explanation = t.explain().unwrap();
+        assert!(explanation.starts_with(
+            "Two consecutive RED nodes, see val: 1 at root/L/L\n\
+             rule: there are no two consecutive RED nodes, a RED node has only BLACK children\n\
+             └─5b\n  ├─7b\n  └─3r ◀\n    └─1r ◀\n\n\
+             Different black heights"
+        ));
+
+        // the node and ancestors it's on the wrong side of, ASCII glyphs
+        let t: RbTree<i32> = "(5b (3r 6b 4b) 7b)".parse().unwrap();
+        let mut out = String::new();
+        let options = PrintOptions {
+            glyphs: Glyphs::Ascii,
+            ..Default::default()
+        };
+        t.explain_violations(&mut out, &t.validate().unwrap_err(), &options)
+            .unwrap();
+        assert!(out.ends_with("`-5b <-\n  |-7b\n  `-3r <-\n    |-4b\n    `-6b <-\n"));
+
+        // a big tree is shown around the node, many paths are grouped
+        let mut t = RbTree::new();
+        for v in 0..200 {
+            t.add(v);
+        }
+        let t: RbTree<i32> = t.to_brackets().replacen("(51b", "(51r", 1).parse().unwrap();
+        let explanation = t.explain().unwrap();
+        assert!(explanation.contains("subtree at root/L:\n└─31b\n"));
+        assert!(explanation.contains(
+            "  root/L/R/L/L/L/L/L  7, 23 more paths with 7\n  \
+             root/L/R/R/L/L/L/L  6, 7 more paths with 6\n"
+        ));
+
+        let mut t = RbTree::new();
+        t.add(1);
+        assert!(t.validate_llrb().is_ok());
+        t.add(2);
+        let mut out = String::new();
+        t.explain_violations(&mut out, &t.validate_llrb().unwrap_err(), &options)
+            .unwrap();
+        assert_eq!(
+            out,
+            "RED node is a right child, see val: 2 at root/R\n\
+             rule: a left-leaning tree has RED nodes only as left children\n\
+             `-1b\n  |-2r <-\n"
+        );
+    }
+
+    // every shape with values lo..hi in the bracket form
+    fn shapes(lo: i32, hi: i32) -> Vec<String> {
+        if lo == hi {
+            return vec!["_".to_string()];
+        }
+        let mut all = Vec::new();
+        for root in lo..hi {
+            for l in shapes(lo, root) {
+                for r in shapes(root + 1, hi) {
+                    all.push(match (l.as_str(), r.as_str()) {
+                        ("_", "_") => format!("{}b", root),
+                        (_, "_") => format!("({}b {})", root, l),
+                        _ => format!("({}b {} {})", root, l, r),
+                    });
+                }
+            }
+        }
+        all
+    }
+
+    fn paint(t: &RbTree<i32>, colors: &[Color]) {
+        let mut colors = colors.iter();
+        let mut stack: Vec<Node<i32>> = t.root.iter().cloned().collect();
+        while let Some(node) = stack.pop() {
+            node.borrow_mut().color = *colors.next().unwrap();
+            for child in node.borrow().children.iter().rev().flatten() {
+                stack.push(child.clone());
+            }
+        }
+    }
+
+    // colors in preorder, set bits are RED
+    fn colors(n: i32, mask: u32) -> Vec<Color> {
+        (0..n)
+            .map(|i| {
+                if mask & (1 << i) != 0 {
+                    Color::Red
+                } else {
+                    Color::Black
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_recolor() {
+        // the solver agrees with trying every coloring
+        for n in 0..=7 {
+            for shape in shapes(0, n) {
+                let mut t: RbTree<i32> = shape.parse().unwrap();
+                let brute = (0..1u32 << n).any(|mask| {
+                    paint(&t, &colors(n, mask));
+                    t.validate().is_ok()
+                });
+
+                match t.find_coloring() {
+                    Ok(coloring) => {
+                        assert!(brute, "{}", shape);
+                        paint(&t, &coloring.colors);
+                        assert!(t.is_valid());
+                        assert_eq!(t.black_height(), coloring.black_height);
+                    }
+                    Err(certificate) => {
+                        assert!(!brute, "{}", shape);
+                        assert!(t.recolor().is_err());
+                        let common = certificate
+                            .left
+                            .iter()
+                            .filter(|h| certificate.right.contains(h));
+                        assert_eq!(common.count(), 0);
+                    }
+                }
+            }
+        }
+
+        // the right subtree always has a BLACK node, the left one is NIL
+        let mut t: RbTree<i32> = "(1b _ (2b _ 3b))".parse().unwrap();
+        let certificate = t.recolor().unwrap_err();
+        assert_eq!(
+            certificate.to_string(),
+            "No coloring exists, subtrees of val: 1 at root can't have the same black height, \
+             left: [0] with paths to NIL of 0 to 0 nodes, \
+             right: [1] with paths to NIL of 1 to 2 nodes"
+        );
+        let t: RbTree<i32> = "(1b 0b (2b _ 3b))".parse().unwrap();
+        assert_eq!(
+            t.find_coloring().unwrap().colors,
+            [Color::Black, Color::Black, Color::Black, Color::Red]
+        );
+
+        // a random valid tree painted BLACK gets its colors back
+        let mut t = RbTree::new();
+        let mut rng = rand::thread_rng();
+        for _ in 0..N {
+            t.add(rng.gen_range(0..MAX));
+        }
+        let black_height = t.black_height();
+        paint(&t, &vec![Color::Black; N]);
+        assert!(t.validate().is_err());
+
+        // every RED node is a recolor seen by the observer
+        let recolors = Rc::new(RefCell::new(0));
+        let seen = recolors.clone();
+        t.set_observer(Box::new(move |e: &Event<i32>| {
+            if let Event::Recolor { .. } = e {
+                *seen.borrow_mut() += 1;
+            }
+        }));
+        assert!(t.recolor().unwrap() >= black_height);
+        assert!(t.validate().is_ok());
+        assert_eq!(*recolors.borrow(), t.stats().red);
+    }
+
+    #[test]
+    fn test_valid_trees() {
+        // the same trees as every shape with every coloring filtered by `validate`
+        for n in 0..=7 {
+            let mut brute = HashSet::new();
+            for shape in shapes(1, n + 1) {
+                let t: RbTree<i32> = shape.parse().unwrap();
+                for mask in 0..1u32 << n {
+                    paint(&t, &colors(n, mask));
+                    if t.validate().is_ok() {
+                        brute.insert(t.to_brackets());
+                    }
+                }
+            }
+
+            let trees = ValidTrees::new(n as usize);
+            assert_eq!(trees.total(), brute.len() as u128);
+            assert_eq!(trees.size_hint(), (brute.len(), Some(brute.len())));
+            let mut count = 0;
+            for t in trees {
+                assert!(t.validate().is_ok());
+                assert!(t.iter().eq(1..=n));
+                assert!(brute.contains(&t.to_brackets()));
+                count += 1;
+            }
+            assert_eq!(count, brute.len());
+        }
+        assert_eq!(ValidTrees::new(11).total(), 330);
+        let mut trees = ValidTrees::new(11);
+        assert_eq!(trees.nth(329).map(|t| t.len()), Some(11));
+        assert!(trees.next().is_none());
+
+        // every add and remove from every starting state, the sorted vector is the model
+        for n in 0..=9 {
+            for start in ValidTrees::new(n) {
+                let start_values = values(&start);
+                for strategy in STRATEGIES {
+                    for v in 0..=n as i32 + 1 {
+                        let context = || format!("{} {:?} v: {}", start.to_brackets(), strategy, v);
+                        let mut t = start.clone();
+                        t.set_strategy(strategy);
+                        let mut model = start_values.clone();
+
+                        assert_eq!(t.remove(&v), model.contains(&v), "{}", context());
+                        model.retain(|&m| m != v);
+                        assert!(t.validate().is_ok(), "{}", context());
+                        assert_eq!(values(&t), model, "{}", context());
+
+                        // a duplicate of an existing value, removed twice
+                        let mut t = start.clone();
+                        t.set_strategy(strategy);
+                        let mut model = start_values.clone();
+                        t.add(v);
+                        model.insert(model.partition_point(|&m| m <= v), v);
+                        assert!(t.validate().is_ok(), "{}", context());
+                        assert_eq!(values(&t), model, "{}", context());
+                        for _ in 0..2 {
+                            let found = model.binary_search(&v);
+                            assert_eq!(t.remove(&v), found.is_ok(), "{}", context());
+                            if let Ok(i) = found {
+                                model.remove(i);
+                            }
+                            assert!(t.validate().is_ok(), "{}", context());
+                            assert_eq!(values(&t), model, "{}", context());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_worst_case() {
+        let replay = |found: &Found| {
+            let mut t = RbTree::with_strategy(found.strategy);
+            for step in &found.steps {
+                match *step {
+                    Step::Add(v) => t.add(v),
+                    Step::Remove(v) => assert!(t.remove(&v), "{}", step),
+                }
+            }
+            t
+        };
+
+        // small n are searched through, so the values are the exact maximums
+        let cases = [
+            (Goal::Height, 4, 3),
+            (Goal::Height, 6, 4),
+            (Goal::BlackHeight, 3, 2),
+            (Goal::BlackHeight, 5, 2),
+            (Goal::Rotations, 5, 3),
+        ];
+        for (goal, n, expected) in cases {
+            let options = SearchOptions {
+                goal,
+                exhaustive_up_to: 6,
+                ..SearchOptions::default()
+            };
+            let found = worst_case(n, &options);
+            assert!(found.exhaustive);
+            assert_eq!(found.value, expected, "{} of {}", goal, n);
+            assert!(found.value as f64 <= found.bound);
+            assert_eq!(found.tree.len(), n);
+            assert_eq!(replay(&found).to_brackets(), found.tree.to_brackets());
+        }
+
+        let options = SearchOptions {
+            goal: Goal::Rotations,
+            strategy: Strategy::TopDown,
+            ..SearchOptions::default()
+        };
+        for n in 0..=5 {
+            let found = worst_case(n, &options);
+            assert_eq!(found.tree.len(), n);
+            assert!(found.to_string().contains(&format!(" of {} values", n)));
+        }
+        let found = worst_case(4, &options);
+        assert!(found.value as f64 <= found.bound);
+        assert_eq!(replay(&found).to_brackets(), found.tree.to_brackets());
+
+        // the same seed gives the same sequence
+        let options = SearchOptions {
+            iterations: 200,
+            seed: 7,
+            ..SearchOptions::default()
+        };
+        let found = worst_case(30, &options);
+        assert!(!found.exhaustive);
+        assert_eq!(worst_case(30, &options).steps, found.steps);
+        assert!(found.value as f64 <= found.bound);
+        assert!(found.tree.validate().is_ok());
+        assert_eq!(found.tree.len(), 30);
+        assert_eq!(replay(&found).to_brackets(), found.tree.to_brackets());
+
+        let text = found.to_string();
+        assert!(text.starts_with(&format!(
+            "# height {} of 30 values, the bound is 9.91",
+            found.value
+        )));
+        assert!(text.contains("# heuristic search, BottomUp"));
+        assert_eq!(
+            text.lines().filter(|l| !l.starts_with('#')).count(),
+            found.steps.len()
+        );
+    }
+
+    #[test]
+    fn test_234() {
+        use Color::*;
+
+        let mut t = RbTree::<i32>::new();
+        assert_eq!(t.to_234().map(|b| b.is_empty()), Ok(true));
+        for v in [5, 3, 8, 1] {
+            t.add(v);
+        }
+        let b = t.to_234().unwrap();
+        assert_eq!(b.to_string(), "└─[5]\n  ├─[8]\n  └─[1 3]\n");
+        assert_eq!(
+            b.nodes[1],
+            Node234 {
+                keys: vec![1, 3],
+                children: vec![None; 3]
+            }
+        );
+
+        // keys in order, as a 2-3-4 tree sees them
+        fn keys(b: &Tree234<i32>, i: Option<usize>, out: &mut Vec<i32>) {
+            if let Some(i) = i {
+                let node = &b.nodes[i];
+                for (k, child) in node.keys.iter().zip(&node.children) {
+                    keys(b, *child, out);
+                    out.push(*k);
+                }
+                keys(b, node.children[node.keys.len()], out);
+            }
+        }
+
+        let mut rng = rand::thread_rng();
+        for strategy in STRATEGIES {
+            let mut t = RbTree::with_strategy(strategy);
+            for step in 0..N {
+                t.add(rng.gen_range(0..100));
+                if step % 3 == 0 {
+                    t.remove(&rng.gen_range(0..100));
+                }
+                if step % 50 != 0 {
+                    continue;
+                }
+
+                let b = t.to_234().unwrap();
+                assert!(b.is_valid());
+                assert_eq!(b.height(), t.black_height());
+                assert_eq!(b.len(), t.len());
+                let mut vals = Vec::new();
+                keys(&b, b.root, &mut vals);
+                assert_eq!(vals, values(&t));
+
+                let back = b.to_rb_tree().unwrap();
+                assert!(back.is_valid());
+                assert_eq!(back.len(), t.len());
+                assert_eq!(back.to_234(), Ok(b));
+            }
+        }
+
+        // merging works, but leaves are on different levels
+        let t = tree(node(5, Black, Some(node(3, Black, None, None)), None), 2);
+        let b = t.to_234().unwrap();
+        assert!(!b.is_valid());
+        assert_eq!(b.to_string(), "└─[5]\n  └─[3]\n");
+
+        let t = tree(
+            node(
+                5,
+                Black,
+                Some(node(3, Red, Some(node(1, Red, None, None)), None)),
+                None,
+            ),
+            3,
+        );
+        assert_eq!(
+            t.to_234(),
+            Err(Violation::RedRed {
+                value: 1,
+                path: vec![Side::Left, Side::Left]
+            })
+        );
+
+        // a node with 4 keys has no RbTree form
+        let b = Tree234 {
+            nodes: vec![Node234 {
+                keys: vec![1, 2, 3, 4],
+                children: vec![None; 5],
+            }],
+            root: Some(0),
+        };
+        assert!(!b.is_valid());
+        assert!(b.to_rb_tree().is_none());
+
+        // hand-built indexes out of range and cycles are rejected, not followed
+        let node = |children| Node234 {
+            keys: vec![5],
+            children,
+        };
+        let out_of_range = Tree234 {
+            nodes: vec![node(vec![Some(7), None])],
+            root: Some(0),
+        };
+        let cycle = Tree234 {
+            nodes: vec![node(vec![Some(0), Some(0)])],
+            root: Some(0),
+        };
+        for (b, printed) in [
+            (out_of_range, "└─[5]\n  └─[?]\n"),
+            (cycle, "└─[5]\n  ├─[?]\n  └─[?]\n"),
+        ] {
+            assert!(!b.is_valid());
+            assert!(b.to_rb_tree().is_none());
+            assert_eq!(b.height(), 1);
+            assert_eq!(b.to_string(), printed);
+        }
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut t = RbTree::<i32>::new();
+        let stats = t.stats();
+        assert_eq!((stats.len, stats.height, stats.black_height), (0, 0, 0));
+        assert!(stats.depths.is_empty());
+        assert_eq!(stats.average_search_depth(), 0.0);
+
+        for v in 1..=10 {
+            t.add(v);
+        }
+        let stats = t.stats();
+        assert_eq!(stats.len, 10);
+        assert_eq!(stats.height, t.height());
+        assert_eq!(stats.black_height, t.black_height());
+        assert_eq!(stats.red + stats.black, 10);
+        assert_eq!(stats.depths.iter().sum::<usize>(), 10);
+        assert_eq!(stats.depths.len(), stats.height);
+        assert_eq!(stats.depths[0], 1);
+        assert!(stats.within_bound());
+        assert!((stats.height_bound() - 2.0 * 11f64.log2()).abs() < 1e-9);
+
+        let visited: usize = (1..=10).map(|v| t.search_path(&v).len()).sum();
+        assert!((stats.average_search_depth() - visited as f64 / 10.0).abs() < 1e-9);
+        assert!(stats.memory > 10 * std::mem::size_of::<RbTreeNode<i32>>());
+
+        let report = stats.to_string();
+        assert!(report.starts_with(&format!(
+            "len: 10 (red: {}, black: {})\nheight: {}, bound 2*log2(n+1): 6.92\n",
+            stats.red, stats.black, stats.height
+        )));
+        assert!(report.ends_with(&format!(
+            "   2 | {} 4\n   3 | {:<40} 2\n   4 | {:<40} 1\n",
+            "#".repeat(40),
+            "#".repeat(20),
+            "#".repeat(10)
+        )));
+
+        // stats of a broken tree are still there
+        let red = node(3, Color::Red, None, None);
+        let t = tree(node(5, Color::Red, Some(red), None), 2);
+        let stats = t.stats();
+        assert_eq!((stats.red, stats.black, stats.black_height), (2, 0, 0));
+        assert!(t.validate().is_err());
+    }
+
+    #[test]
+    fn test_observer() {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut t = RbTree::<i32>::new();
+
+        let sink = events.clone();
+        t.set_observer(Box::new(move |e: &Event<i32>| {
+            sink.borrow_mut().push(e.clone())
+        }));
+        t.add(1);
+        t.add(2);
+        // nothing to balance
+        assert_eq!(
+            *events.borrow(),
+            vec![
+                Event::Operation(Operation::Add),
+                Event::Operation(Operation::Add),
+                Event::Compare(2),
+            ]
+        );
+        events.borrow_mut().clear();
+
+        t.add(3);
+        assert_eq!(
+            *events.borrow(),
+            vec![
+                Event::Operation(Operation::Add),
+                Event::Compare(3),
+                Event::InsertCase(2),
+                Event::Recolor {
+                    node: 2,
+                    from: Color::Red,
+                    to: Color::Black
+                },
+                Event::Recolor {
+                    node: 1,
+                    from: Color::Black,
+                    to: Color::Red
+                },
+                Event::Rotate {
+                    dir: Rotation::Left,
+                    pivot: 2
+                },
+            ]
+        );
+        assert!(t.take_observer().is_some());
+
+        // the tree is consistent on every event, all nodes are reachable from the root
+        struct Counter(Vec<usize>);
+        impl Observer<i32> for Rc<RefCell<Counter>> {
+            fn on_event(&mut self, e: &Event<i32>, root: Option<&Node<i32>>) {
+                // the search happens before the new node is attached
+                if !matches!(e, Event::Operation(_) | Event::Compare(_)) {
+                    self.borrow_mut().0.push(count(root));
+                }
+            }
+        }
+
+        let counter = Rc::new(RefCell::new(Counter(Vec::new())));
+        t.set_observer(Box::new(counter.clone()));
+        for v in 4..100 {
+            let len = t.len();
+            t.add(v);
+            assert!(counter.borrow().0.iter().all(|&n| n == len + 1));
+            counter.borrow_mut().0.clear();
+        }
+        for v in 1..100 {
+            let len = t.len();
+            t.remove(&v);
+            assert!(counter.borrow().0.iter().all(|&n| n == len || n == len - 1));
+            counter.borrow_mut().0.clear();
+        }
+    }
+
+    #[test]
+    fn test_counters() {
+        let counters = Rc::new(RefCell::new(CostCounters::default()));
+        let mut t = RbTree::<i32>::new();
+        t.set_observer(Box::new(counters.clone()));
+
+        t.add(1);
+        t.add(2);
+        t.add(3);
+        assert_eq!(
+            counters.borrow().last,
+            Costs {
+                comparisons: 3,
+                rotations: 1,
+                left_rotations: 1,
+                right_rotations: 0,
+                recolors: 2,
+                swaps: 0
+            }
+        );
+
+        let ops = stress::generate(&Config {
+            seed: 3,
+            steps: 5000,
+            max: 1000,
+            ..Default::default()
+        });
+        let (mut adds, mut removes) = (3, 0);
+        for op in ops {
+            match op {
+                Op::Add(v) => {
+                    t.add(v);
+                    adds += 1;
+                }
+                Op::Remove(v) => {
+                    t.remove(&v);
+                    removes += 1;
+                }
+                Op::Contains(_) => {}
+            }
+        }
+
+        let c = counters.borrow().snapshot();
+        assert_eq!(c.uncovered(), vec![]);
+        assert_eq!((c.adds, c.removes), (adds, removes));
+        assert!(c.insert_cases.iter().all(|&n| n > 0));
+
+        // insertion needs at most two rotations and amortized O(1) of them,
+        // removal needs at most three
+        assert!(c.max_add.rotations <= 2);
+        assert!(c.max_remove.rotations <= 3);
+        assert!((c.total_add.rotations as f64) < c.adds as f64);
+        assert!(c.max_remove.swaps <= 1);
+        assert!(c.max_add.comparisons <= t.height() + 2);
+
+        let report = c.to_string();
+        assert!(report.contains("insert cases: 1: "));
+        assert!(report
+            .lines()
+            .next()
+            .unwrap()
+            .ends_with("count  compare   rotate  recolor   swap"));
+
+        // nothing is uncovered only when every case ran
+        let fresh = CostCounters::default();
+        assert_eq!(fresh.uncovered().len(), 7);
+        assert_eq!(fresh.uncovered()[3], (Operation::Remove, 1));
+
+        let mut counters = CostCounters::default();
+        for event in [
+            Event::InsertCase(0),
+            Event::InsertCase(4),
+            Event::RemoveCase(9),
+        ] {
+            counters.on_event::<i32>(&event);
+        }
+        counters.on_event::<i32>(&Event::RemoveCase(4));
+        assert_eq!(counters.insert_cases, [0; 3]);
+        assert_eq!(counters.remove_cases, [0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_playback() {
+        let mut t = RbTree::<i32>::new();
+        t.add(1);
+        t.add(2);
+
+        let events = Rc::new(RefCell::new(0));
+        let sink = events.clone();
+        t.set_observer(Box::new(move |_: &Event<i32>| *sink.borrow_mut() += 1));
+
+        let recording = t.add_recorded(3);
+        let captions: Vec<&str> = recording
+            .frames
+            .iter()
+            .map(|f| f.caption.as_str())
+            .collect();
+        assert_eq!(
+            captions,
+            vec![
+                "before",
+                "recolor 2 Red -> Black",
+                "recolor 1 Black -> Red",
+                "rotate Left with pivot 2",
+                "after"
+            ]
+        );
+        assert!(recording.frames[1..4]
+            .iter()
+            .all(|f| f.case == Some(Event::InsertCase(2))));
+        assert_eq!(
+            recording.frames[3].to_string(),
+            "insert case 2: uncle is BLACK, node and parent are on the same side, rotate grandparent\n\
+             rotate Left with pivot 2\n\
+             └─2b ◀\n  ├─3r\n  └─1r\n"
+        );
+        assert_eq!(recording.frames[4].tree.to_string(), t.to_string());
+
+        // the previous observer is back and got all events
+        assert_eq!(*events.borrow(), 6);
+        t.add(4);
+        assert!(*events.borrow() > 6);
+
+        let (removed, recording) = t.remove_recorded(&1);
+        assert!(removed);
+        assert_eq!(recording.frames[0].changed, vec![1]);
+        assert_eq!(recording.frames[0].tree.len(), 4);
+        assert_eq!(recording.frames.last().unwrap().tree.len(), 3);
+        assert!(recording.frames.iter().any(|f| f
+            .case
+            .as_ref()
+            .is_some_and(|c| matches!(c, Event::RemoveCase(_)))));
+        assert!(recording.frames.last().unwrap().tree.validate().is_ok());
+
+        let (removed, recording) = t.remove_recorded(&100);
+        assert!(!removed);
+        assert_eq!(recording.frames.len(), 2);
+
+        let mut buf = Vec::new();
+        recording.play(&mut buf, std::time::Duration::ZERO).unwrap();
+        let played = String::from_utf8(buf).unwrap();
+        assert!(played.contains("remove 100 - step 2/2"));
     }
 }
@@ -0,0 +1,152 @@
+use std::fmt::Write as _;
+
+use super::{ancestor::Pos, node::*, RbTree};
+
+const PREAMBLE: &str = "\
+% requires \\usepackage{forest}
+\\begin{forest}
+  for tree={circle, draw, minimum size=2em, inner sep=1pt, font=\\small,
+    s sep=4mm, l sep=6mm, edge={thick}},
+  rb black/.style={fill=black, text=white},
+  rb red/.style={fill=red!80!black, draw=red!80!black, text=white},
+  rb nil/.style={rectangle, fill=black, minimum size=0.6em, inner sep=0pt},
+";
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TikzOptions {
+    // draw NIL leaves as small black boxes
+    pub nil_leaves: bool,
+    // label every node with its black height
+    pub black_height: bool,
+}
+
+impl<T> RbTree<T>
+where
+    T: std::fmt::Debug + std::cmp::Ord + std::cmp::Eq + std::fmt::Display,
+{
+    // renders the tree as `forest` environment for LaTeX documents
+    pub fn to_tikz(&self) -> String {
+        self.to_tikz_with(&TikzOptions::default())
+    }
+
+    pub fn to_tikz_with(&self, options: &TikzOptions) -> String {
+        Self::forest(self.root.as_ref(), options)
+    }
+
+    // renders only the subtree rooted at the value, None if there is no such value
+    pub fn subtree_to_tikz(&self, val: &T, options: &TikzOptions) -> Option<String> {
+        let node = self.search_path(val).pop()?;
+        if node.borrow().val != *val {
+            return None;
+        }
+        Some(Self::forest(Some(&node), options))
+    }
+
+    fn forest(root: Option<&Node<T>>, options: &TikzOptions) -> String {
+        let mut out = String::from(PREAMBLE);
+        match root {
+            Some(root) => {
+                Self::write_forest(&mut out, root, options);
+            }
+            // forest can't draw an empty tree, draw a single NIL instead
+            None => out += "  [, rb nil]\n",
+        }
+        out += "\\end{forest}\n";
+        out
+    }
+    // uses explicit stacks instead of recursion, the first pass collects the
+    // nodes in preorder for their black heights, the second one writes them
+    fn write_forest(out: &mut String, root: &Node<T>, options: &TikzOptions) {
+        // (node, indexes of the children), every parent comes before its children
+        let mut nodes: Vec<(Node<T>, [Option<usize>; 2])> = Vec::new();
+        let mut stack = vec![(root.clone(), None::<(usize, usize)>)];
+        while let Some((node, parent)) = stack.pop() {
+            let i = nodes.len();
+            if let Some((p, pos)) = parent {
+                nodes[p].1[pos] = Some(i);
+            }
+            for pos in [Pos::RIGHT, Pos::LEFT] {
+                if let Some(child) = node.borrow().children[pos].as_ref() {
+                    stack.push((child.clone(), Some((i, pos))));
+                }
+            }
+            nodes.push((node, [None, None]));
+        }
+
+        // the same way as `black_height` counts, NIL nodes are not included,
+        // walking backwards gets the left child before its parent
+        let mut heights = vec![0; nodes.len()];
+        for i in (0..nodes.len()).rev() {
+            let (node, children) = &nodes[i];
+            heights[i] = children[Pos::LEFT].map_or(0, |l| heights[l])
+                + (node.borrow().color == Color::Black) as usize;
+        }
+
+        enum Item {
+            // (index, level)
+            Node(usize, usize),
+            Line(String),
+        }
+
+        let mut stack = vec![Item::Node(0, 1)];
+        while let Some(item) = stack.pop() {
+            let (i, level) = match item {
+                Item::Line(line) => {
+                    out.push_str(&line);
+                    continue;
+                }
+                Item::Node(i, level) => (i, level),
+            };
+            let (node, children) = &nodes[i];
+            let r = node.borrow();
+            let indent = "  ".repeat(level);
+
+            let style = match r.color {
+                Color::Red => "rb red",
+                Color::Black => "rb black",
+            };
+            let _ = write!(
+                out,
+                "{}[{{{}}}, {}",
+                indent,
+                escape(&r.val.to_string()),
+                style
+            );
+            if options.black_height {
+                let _ = write!(out, ", label={{[font=\\scriptsize]right:{}}}", heights[i]);
+            }
+            out.push('\n');
+
+            stack.push(Item::Line(format!("{}]\n", indent)));
+            if children.iter().any(|c| c.is_some()) || options.nil_leaves {
+                for pos in [Pos::RIGHT, Pos::LEFT] {
+                    stack.push(match children[pos] {
+                        Some(child) => Item::Node(child, level + 1),
+                        None if options.nil_leaves => {
+                            Item::Line(format!("{}  [, rb nil]\n", indent))
+                        }
+                        // keeps the only child on its side
+                        None => Item::Line(format!("{}  [, phantom]\n", indent)),
+                    });
+                }
+            }
+        }
+    }
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out += "\\textbackslash{}",
+            '~' => out += "\\textasciitilde{}",
+            '^' => out += "\\textasciicircum{}",
+            '#' | '$' | '%' | '&' | '_' | '{' | '}' => {
+                out.push('\\');
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,353 @@
+use std::{cmp::Ordering, mem, rc::Rc};
+
+use super::{ancestor::Pos, node::*, Event, RbTree, Rotation};
+
+// how `add` and `remove` keep the tree balanced, both give valid trees,
+// so the strategy could be changed at any time
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Strategy {
+    // find the place first, then fix colors on the way back up the path
+    BottomUp,
+    // recolor and rotate on the way down, no path is kept and nothing is
+    // left to fix when the bottom is reached
+    TopDown,
+}
+
+impl<T> RbTree<T>
+where
+    T: std::fmt::Debug + std::cmp::Ord + std::cmp::Eq + std::fmt::Display,
+{
+    pub fn with_strategy(strategy: Strategy) -> Self {
+        let mut tree = Self::new();
+        tree.strategy = strategy;
+        tree
+    }
+
+    pub fn strategy(&self) -> Strategy {
+        self.strategy
+    }
+
+    pub fn set_strategy(&mut self, strategy: Strategy) {
+        self.strategy = strategy;
+    }
+
+    // splits every BLACK node with two RED children on the way down,
+    // so the new RED leaf never gets a RED uncle
+    //
+    // the tree shouldn't be empty, `add` fixes the root color and the length
+    pub(super) fn add_top_down(&mut self, val: T) {
+        // None above the root stands for the root slot of the tree
+        let mut great: Option<Node<T>> = None;
+        let mut grand: Option<Node<T>> = None;
+        let mut parent: Option<Node<T>> = None;
+        let mut node = self.root.clone().unwrap();
+        // sides of the node in the parent and of the parent in the grandparent
+        let mut side = Pos::LEFT;
+        let mut parent_side = Pos::LEFT;
+        let mut compared = 0;
+
+        loop {
+            if Self::red_children(node.clone()) == 0b11 {
+                // case 1: color flip, the same as RED uncle bottom-up
+                //     n          n
+                //    / \   ->   / \
+                //   c   c      c   c
+                // BLACK n with RED children becomes RED with BLACK ones
+                self.emit(|| Event::InsertCase(1));
+
+                self.set_color(&node, Color::Red);
+                let children = node.borrow().children.clone();
+                for child in children.iter().flatten() {
+                    self.set_color(child, Color::Black);
+                }
+                self.fix_red(
+                    great.as_ref(),
+                    grand.as_ref(),
+                    parent.as_ref(),
+                    &node,
+                    side,
+                    parent_side,
+                );
+            }
+
+            compared += 1;
+            let dir = if val <= node.borrow().val {
+                Pos::LEFT
+            } else {
+                Pos::RIGHT
+            };
+            let child = node.borrow().children[dir].clone();
+
+            match child {
+                Some(next) => {
+                    great = grand;
+                    grand = parent;
+                    parent = Some(node);
+                    node = next;
+                    parent_side = side;
+                    side = dir;
+                }
+                None => {
+                    self.emit(|| Event::Compare(compared));
+
+                    let new_one = Self::new_node(val, Color::Red);
+                    node.borrow_mut().children[dir] = Some(new_one.clone());
+                    self.fix_red(
+                        grand.as_ref(),
+                        parent.as_ref(),
+                        Some(&node),
+                        &new_one,
+                        dir,
+                        side,
+                    );
+                    return;
+                }
+            }
+        }
+    }
+
+    // RED node under RED parent, the grandparent is BLACK because
+    // splits above made sure there is no RED uncle
+    //
+    // a rotation happens at most once per two levels, so nodes above
+    // the grandparent are never stale when they are used
+    fn fix_red(
+        &mut self,
+        great: Option<&Node<T>>,
+        grand: Option<&Node<T>>,
+        parent: Option<&Node<T>>,
+        node: &Node<T>,
+        side: usize,
+        parent_side: usize,
+    ) {
+        if !Self::is_red(Some(node)) || !Self::is_red(parent) {
+            return;
+        }
+        let grand = grand.unwrap();
+        let dir = Self::opposite_pos(parent_side);
+
+        if side == parent_side {
+            // case 2: node and parent are on the same side,
+            // parent takes place of grandparent
+            self.emit(|| Event::InsertCase(2));
+            self.rotate_down(great, grand, dir);
+        } else {
+            // case 3: node and parent are on different sides,
+            // node takes place of grandparent
+            self.emit(|| Event::InsertCase(3));
+            self.rotate_down_twice(great, grand, dir);
+        }
+    }
+
+    // makes the node on the way down RED, so the node to delete is RED
+    // when the bottom is reached and could be removed without any fixing
+    //
+    // the node with the value is kept and replaced by its predecessor
+    pub(super) fn remove_top_down(&mut self, val: &T) -> bool {
+        // None above the root stands for the root slot of the tree,
+        // the node is None only before the first step
+        let mut grand: Option<Node<T>>;
+        let mut parent: Option<Node<T>> = None;
+        let mut node: Option<Node<T>> = None;
+        let mut dir = Pos::LEFT;
+        let mut found: Option<Node<T>> = None;
+        let mut compared = 0;
+
+        loop {
+            let next = match &node {
+                Some(n) => n.borrow().children[dir].clone(),
+                None => self.root.clone(),
+            };
+            let current = match next {
+                Some(n) => n,
+                None => break,
+            };
+            let last = dir;
+            grand = parent;
+            parent = node;
+            node = Some(current.clone());
+
+            compared += 1;
+            // equal values go left, so the last equal one on the path is found
+            // and the way continues to its predecessor
+            let ord = current.borrow().val.cmp(val);
+            if ord == Ordering::Equal {
+                found = Some(current.clone());
+            }
+            dir = if ord == Ordering::Less {
+                Pos::RIGHT
+            } else {
+                Pos::LEFT
+            };
+            let other = Self::opposite_pos(dir);
+
+            // nothing to do if the node or the next one is RED already
+            if Self::is_red(Some(&current)) || Self::is_red(Self::child(&current, dir).as_ref()) {
+                continue;
+            }
+
+            if Self::is_red(Self::child(&current, other).as_ref()) {
+                // case 1: the other child is RED, the same as RED sibling bottom-up
+                //     n          o
+                //    / \        / \
+                //  next o  ->  n   ?
+                //     / \     / \
+                //    ?   ?  next ?
+                // n becomes RED and stays the parent of the next node
+                self.emit(|| Event::RemoveCase(1));
+                let top = self.rotate_down(parent.as_ref(), &current, dir);
+                parent = Some(top);
+                continue;
+            }
+
+            // the root has no sibling
+            let (parent_node, sibling) = match &parent {
+                Some(p) => match Self::child(p, Self::opposite_pos(last)) {
+                    Some(s) => (p.clone(), s),
+                    None => continue,
+                },
+                None => continue,
+            };
+            let near = Self::child(&sibling, last);
+            let far = Self::child(&sibling, Self::opposite_pos(last));
+
+            if !Self::is_red(near.as_ref()) && !Self::is_red(far.as_ref()) {
+                // case 2: both nephews are BLACK, parent is RED or the root,
+                // it becomes BLACK and both its children RED
+                self.emit(|| Event::RemoveCase(2));
+                self.set_color(&parent_node, Color::Black);
+                self.set_color(&sibling, Color::Red);
+                self.set_color(&current, Color::Red);
+            } else {
+                let top = if Self::is_red(near.as_ref()) {
+                    // case 4: near nephew is RED, it takes place of the parent
+                    self.emit(|| Event::RemoveCase(4));
+                    self.rotate_down_twice(grand.as_ref(), &parent_node, last)
+                } else {
+                    // case 3: far nephew is RED, sibling takes place of the parent
+                    self.emit(|| Event::RemoveCase(3));
+                    self.rotate_down(grand.as_ref(), &parent_node, last)
+                };
+
+                // the top keeps the color parent had, node becomes RED
+                self.set_color(&top, Color::Red);
+                let children = top.borrow().children.clone();
+                for child in children.iter().flatten() {
+                    self.set_color(child, Color::Black);
+                }
+                self.set_color(&current, Color::Red);
+            }
+        }
+        self.emit(|| Event::Compare(compared));
+
+        let removed = match (found, node) {
+            (Some(found), Some(last)) => {
+                if !Rc::ptr_eq(&found, &last) {
+                    mem::swap(&mut found.borrow_mut().val, &mut last.borrow_mut().val);
+                    self.emit_with(|copy| Event::SwapNodes {
+                        a: copy(&last.borrow().val),
+                        b: copy(&found.borrow().val),
+                    });
+                }
+
+                // the last node has at most one child
+                let child = {
+                    let mut r = last.borrow_mut();
+                    let [left, right] = &mut r.children;
+                    left.take().or_else(|| right.take())
+                };
+                self.replace_child(parent.as_ref(), &last, child);
+                self.emit_with(|copy| Event::Extract {
+                    node: copy(&last.borrow().val),
+                });
+                true
+            }
+            _ => false,
+        };
+
+        if let Some(root) = self.root.clone() {
+            self.set_color(&root, Color::Black);
+        }
+        removed
+    }
+
+    // rotates the node down to the dir side, its child from the other side
+    // takes its place and becomes BLACK, the node becomes RED
+    //
+    // parent None means the node is the root
+    fn rotate_down(&mut self, parent: Option<&Node<T>>, node: &Node<T>, dir: usize) -> Node<T> {
+        let other = Self::opposite_pos(dir);
+        let pivot = node.borrow().children[other].clone().unwrap();
+        // could be None
+        let rest = pivot.borrow().children[dir].clone();
+
+        node.borrow_mut().children[other] = rest;
+        pivot.borrow_mut().children[dir] = Some(node.clone());
+        self.replace_child(parent, node, Some(pivot.clone()));
+
+        self.emit_with(|copy| Event::Rotate {
+            dir: if dir == Pos::LEFT {
+                Rotation::Left
+            } else {
+                Rotation::Right
+            },
+            pivot: copy(&pivot.borrow().val),
+        });
+        self.set_color(node, Color::Red);
+        self.set_color(&pivot, Color::Black);
+        pivot
+    }
+
+    // the grandchild from the inner side takes place of the node
+    //     n          n
+    //    /          /          g
+    //   c    ->    g    ->    / \
+    //    \        /          c   n
+    //     g      c
+    fn rotate_down_twice(
+        &mut self,
+        parent: Option<&Node<T>>,
+        node: &Node<T>,
+        dir: usize,
+    ) -> Node<T> {
+        let other = Self::opposite_pos(dir);
+        let child = node.borrow().children[other].clone().unwrap();
+        self.rotate_down(Some(node), &child, other);
+        self.rotate_down(parent, node, dir)
+    }
+
+    // puts the new node where the old one was, parent None means the root
+    pub(super) fn replace_child(
+        &mut self,
+        parent: Option<&Node<T>>,
+        old: &Node<T>,
+        new: Option<Node<T>>,
+    ) {
+        match parent {
+            Some(p) => {
+                let mut p = p.borrow_mut();
+                let is_left = p.children[Pos::LEFT]
+                    .as_ref()
+                    .is_some_and(|c| Rc::ptr_eq(c, old));
+                let pos = if is_left { Pos::LEFT } else { Pos::RIGHT };
+                debug_assert!(p.children[pos].as_ref().is_some_and(|c| Rc::ptr_eq(c, old)));
+                p.children[pos] = new;
+            }
+            None => {
+                debug_assert!(self.root.as_ref().is_some_and(|r| Rc::ptr_eq(r, old)));
+                self.root = new;
+            }
+        }
+    }
+
+    #[inline]
+    pub(super) fn child(node: &Node<T>, pos: usize) -> Option<Node<T>> {
+        node.borrow().children[pos].clone()
+    }
+
+    // NIL nodes are BLACK
+    #[inline]
+    pub(super) fn is_red(node: Option<&Node<T>>) -> bool {
+        node.is_some_and(|n| n.borrow().color == Color::Red)
+    }
+}
@@ -0,0 +1,331 @@
+use std::fmt;
+
+use super::{ancestor::Pos, node::*, Glyphs, Path, RbTree, Side, Violation};
+
+// a node of 2-3-4 tree, it's a BLACK node of RbTree together with its RED children
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Node234<T> {
+    // 1 to 3 keys in ascending order
+    pub keys: Vec<T>,
+    // indexes in `Tree234::nodes`, one more than keys, None is NIL
+    pub children: Vec<Option<usize>>,
+}
+
+// nodes are kept in one vector and refer to each other by index,
+// so nothing here is recursive, even for a broken tree of any height
+//
+// nodes are stored in preorder, children from left to right, so equal
+// trees have equal vectors
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Tree234<T> {
+    pub nodes: Vec<Node234<T>>,
+    pub root: Option<usize>,
+}
+
+// the node at the index if it's in range and is seen for the first time,
+// fields are public, so a hand-built tree could have any indexes and cycles
+fn visit<'a, T>(nodes: &'a [Node234<T>], seen: &mut [bool], i: usize) -> Option<&'a Node234<T>> {
+    let first = !std::mem::replace(seen.get_mut(i)?, true);
+    nodes.get(i).filter(|_| first)
+}
+
+// index of the parent node and the child slot in it, None for the root
+type Slot = Option<(usize, usize)>;
+// RbTree node and the side a child is linked to, None for the root
+type Link<T> = Option<(Node<T>, usize)>;
+
+impl<T> RbTree<T>
+where
+    T: std::fmt::Debug + std::cmp::Ord + std::cmp::Eq + std::fmt::Display + Clone,
+{
+    // merges every BLACK node with its RED children
+    //
+    //        5b              [3 5 8]
+    //      /    \           /  |  |  \
+    //    3r      8r   ->  [1] [4] [6] [9]
+    //   /  \    /  \
+    //  1b  4b  6b  9b
+    //
+    // fails if the root is RED or a RED node has a RED child,
+    // different black heights give leaves on different levels
+    pub fn to_234(&self) -> Result<Tree234<T>, Violation<T>> {
+        let mut tree = Tree234 {
+            nodes: Vec::new(),
+            root: None,
+        };
+
+        // (BLACK node, its path, where it goes in the 2-3-4 tree)
+        let mut stack: Vec<(Node<T>, Path, Slot)> = Vec::new();
+        if let Some(root) = self.root.as_ref() {
+            if root.borrow().color == Color::Red {
+                return Err(Violation::RedRoot {
+                    value: root.borrow().val.clone(),
+                    path: Vec::new(),
+                });
+            }
+            stack.push((root.clone(), Vec::new(), None));
+        }
+
+        while let Some((node, path, parent)) = stack.pop() {
+            let mut merged = Node234 {
+                keys: Vec::new(),
+                children: Vec::new(),
+            };
+            // BLACK nodes below, they become children of the merged node
+            let mut below = Vec::new();
+
+            let r = node.borrow();
+            for side in [Side::Left, Side::Right] {
+                let mut child_path = path.clone();
+                child_path.push(side);
+
+                match r.children[side.pos()].as_ref() {
+                    Some(child) if child.borrow().color == Color::Red => {
+                        let c = child.borrow();
+                        for grand_side in [Side::Left, Side::Right] {
+                            let mut grand_path = child_path.clone();
+                            grand_path.push(grand_side);
+
+                            let grand = c.children[grand_side.pos()].clone();
+                            if let Some(g) =
+                                grand.as_ref().filter(|g| g.borrow().color == Color::Red)
+                            {
+                                return Err(Violation::RedRed {
+                                    value: g.borrow().val.clone(),
+                                    path: grand_path,
+                                });
+                            }
+                            below.push((grand, grand_path));
+                        }
+                        merged.keys.push(c.val.clone());
+                    }
+                    child => below.push((child.cloned(), child_path)),
+                }
+                if side == Side::Left {
+                    merged.keys.push(r.val.clone());
+                }
+            }
+
+            let index = tree.nodes.len();
+            merged.children = vec![None; below.len()];
+            tree.nodes.push(merged);
+            match parent {
+                Some((p, slot)) => tree.nodes[p].children[slot] = Some(index),
+                None => tree.root = Some(index),
+            }
+
+            // the leftmost goes on top to keep the preorder
+            for (slot, (child, child_path)) in below.into_iter().enumerate().rev() {
+                if let Some(child) = child {
+                    stack.push((child, child_path, Some((index, slot))));
+                }
+            }
+        }
+        Ok(tree)
+    }
+}
+
+impl<T: Ord + Clone> Tree234<T> {
+    // number of keys
+    pub fn len(&self) -> usize {
+        self.nodes.iter().map(|n| n.keys.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    // number of nodes on the leftmost path, the same as the black height of RbTree
+    pub fn height(&self) -> usize {
+        let mut seen = vec![false; self.nodes.len()];
+        let mut height = 0;
+        let mut next = self.root;
+        while let Some(node) = next.and_then(|i| visit(&self.nodes, &mut seen, i)) {
+            height += 1;
+            next = node.children.first().copied().flatten();
+        }
+        height
+    }
+
+    // 2-3-4 rules: 1 to 3 keys in order, one more child than keys,
+    // all NIL children on the same level, the same as all RbTree rules,
+    // every index is in range and is used once
+    pub fn is_valid(&self) -> bool {
+        let mut seen = vec![false; self.nodes.len()];
+        let mut nil_depth = None;
+        // (node, depth, bounds from ancestors)
+        let mut stack = Vec::new();
+        if let Some(root) = self.root {
+            stack.push((root, 1, None, None));
+        }
+
+        while let Some((i, depth, min, max)) = stack.pop() {
+            let node = match visit(&self.nodes, &mut seen, i) {
+                Some(node) => node,
+                None => return false,
+            };
+            let keys = &node.keys;
+            if keys.is_empty() || keys.len() > 3 || node.children.len() != keys.len() + 1 {
+                return false;
+            }
+            if keys.windows(2).any(|w| w[0] > w[1])
+                || min.is_some_and(|m: &T| keys[0] < *m)
+                || max.is_some_and(|m: &T| keys[keys.len() - 1] > *m)
+            {
+                return false;
+            }
+
+            for (slot, child) in node.children.iter().enumerate() {
+                match child {
+                    Some(c) => {
+                        let min = if slot == 0 {
+                            min
+                        } else {
+                            Some(&keys[slot - 1])
+                        };
+                        let max = keys.get(slot).or(max);
+                        stack.push((*c, depth + 1, min, max));
+                    }
+                    None => {
+                        if *nil_depth.get_or_insert(depth) != depth {
+                            return false;
+                        }
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    // splits every node back into a BLACK node with RED children,
+    // the middle key of a 3-key node and the right key of a 2-key one are BLACK
+    //
+    //   [3 5]          5b
+    //                 /
+    //               3r
+    //
+    // None if a node has a wrong number of keys or children,
+    // or an index is out of range or used twice
+    pub fn to_rb_tree(&self) -> Option<RbTree<T>>
+    where
+        T: std::fmt::Debug + std::fmt::Display,
+    {
+        let mut seen = vec![false; self.nodes.len()];
+        let mut tree = RbTree::default();
+
+        // (node index, where it goes in RbTree)
+        let mut stack: Vec<(usize, Link<T>)> = Vec::new();
+        if let Some(root) = self.root {
+            stack.push((root, None));
+        }
+
+        while let Some((i, parent)) = stack.pop() {
+            let node = visit(&self.nodes, &mut seen, i)?;
+            if node.children.len() != node.keys.len() + 1 {
+                return None;
+            }
+            let key = |k: usize, color| RbTree::new_node(node.keys[k].clone(), color);
+
+            // RbTree nodes the children are attached to, from left to right
+            let (top, slots) = match node.keys.len() {
+                1 => {
+                    let b = key(0, Color::Black);
+                    (
+                        b.clone(),
+                        [(b.clone(), Pos::LEFT), (b, Pos::RIGHT)].to_vec(),
+                    )
+                }
+                2 => {
+                    let b = key(1, Color::Black);
+                    let r = key(0, Color::Red);
+                    b.borrow_mut().children[Pos::LEFT] = Some(r.clone());
+                    let slots = [
+                        (r.clone(), Pos::LEFT),
+                        (r, Pos::RIGHT),
+                        (b.clone(), Pos::RIGHT),
+                    ];
+                    (b, slots.to_vec())
+                }
+                3 => {
+                    let b = key(1, Color::Black);
+                    let l = key(0, Color::Red);
+                    let r = key(2, Color::Red);
+                    b.borrow_mut().children = [Some(l.clone()), Some(r.clone())];
+                    let slots = [
+                        (l.clone(), Pos::LEFT),
+                        (l, Pos::RIGHT),
+                        (r.clone(), Pos::LEFT),
+                        (r, Pos::RIGHT),
+                    ];
+                    (b, slots.to_vec())
+                }
+                _ => return None,
+            };
+
+            match parent {
+                Some((p, side)) => p.borrow_mut().children[side] = Some(top),
+                None => tree.root = Some(top),
+            }
+            for (child, slot) in node.children.iter().zip(slots) {
+                if let Some(c) = child {
+                    stack.push((*c, Some(slot)));
+                }
+            }
+            tree.len += node.keys.len();
+        }
+        Some(tree)
+    }
+}
+
+impl<T: fmt::Debug> Tree234<T> {
+    // writes the tree sideways the same way as RbTree, right children are above
+    // └─[3 5 8]
+    //   ├─[9]
+    //   ├─[6]
+    //   ├─[4]
+    //   └─[1]
+    //
+    // a node with a wrong index is written as `[?]`
+    pub fn render<W: fmt::Write>(&self, out: &mut W, glyphs: Glyphs) -> fmt::Result {
+        let (last, other, cont, _) = glyphs.set();
+        let mut seen = vec![false; self.nodes.len()];
+        let mut prefix = String::new();
+
+        // (node, is the last child, length of the prefix in front of the node)
+        let mut stack = Vec::new();
+        if let Some(root) = self.root {
+            stack.push((root, true, 0));
+        }
+
+        while let Some((i, is_last, len)) = stack.pop() {
+            prefix.truncate(len);
+            out.write_str(&prefix)?;
+            out.write_str(if is_last { last } else { other })?;
+            let node = match visit(&self.nodes, &mut seen, i) {
+                Some(node) => node,
+                None => {
+                    writeln!(out, "[?]")?;
+                    continue;
+                }
+            };
+
+            let keys: Vec<String> = node.keys.iter().map(|k| format!("{:?}", k)).collect();
+            writeln!(out, "[{}]", keys.join(" "))?;
+
+            prefix.push_str(if is_last { "  " } else { cont });
+            let len = prefix.len();
+
+            // the leftmost child is printed last, so it's pushed first
+            for (n, child) in node.children.iter().flatten().enumerate() {
+                stack.push((*child, n == 0, len));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Display for Tree234<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.render(f, Glyphs::Unicode)
+    }
+}
@@ -0,0 +1,528 @@
+use std::{cell::RefCell, fmt, mem};
+
+use super::{ancestor::*, node::*, RbTree};
+
+// direction taken from a parent to reach a node
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl Side {
+    pub(crate) fn pos(self) -> usize {
+        match self {
+            Side::Left => Pos::LEFT,
+            Side::Right => Pos::RIGHT,
+        }
+    }
+}
+
+// directions from the root to a node, empty for the root itself
+pub type Path = Vec<Side>;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TreeStats {
+    pub len: usize,
+    // number of nodes on the longest path from the root to a leaf
+    pub height: usize,
+    // number of BLACK nodes on any path from the root to a NIL
+    pub black_height: usize,
+    pub red: usize,
+    pub black: usize,
+    // number of nodes on each level, the root is on level 0
+    pub depths: Vec<usize>,
+    // bytes taken by the tree and its nodes, memory owned by values isn't counted
+    pub memory: usize,
+}
+
+impl TreeStats {
+    // number of nodes visited on average to find a value which is in the tree
+    pub fn average_search_depth(&self) -> f64 {
+        if self.len == 0 {
+            return 0.0;
+        }
+        let visited: usize = self
+            .depths
+            .iter()
+            .enumerate()
+            .map(|(d, n)| (d + 1) * n)
+            .sum();
+        visited as f64 / self.len as f64
+    }
+
+    // the height of any RbTree is not more than 2*log2(n+1)
+    pub fn height_bound(&self) -> f64 {
+        2.0 * (self.len as f64 + 1.0).log2()
+    }
+
+    pub fn within_bound(&self) -> bool {
+        height_fits(self.height, self.len)
+    }
+}
+
+// len: 10 (red: 3, black: 7)
+// height: 4, bound 2*log2(n+1): 6.92
+// ...
+impl fmt::Display for TreeStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "len: {} (red: {}, black: {})",
+            self.len, self.red, self.black
+        )?;
+        writeln!(
+            f,
+            "height: {}, bound 2*log2(n+1): {:.2}{}",
+            self.height,
+            self.height_bound(),
+            if self.within_bound() { "" } else { " EXCEEDED" }
+        )?;
+        writeln!(f, "black height: {}", self.black_height)?;
+        writeln!(
+            f,
+            "average search depth: {:.2}",
+            self.average_search_depth()
+        )?;
+        writeln!(f, "memory: ~{} bytes", self.memory)?;
+
+        // bars are scaled to the widest level
+        writeln!(f, "nodes by depth:")?;
+        let widest = self.depths.iter().copied().max().unwrap_or(0).max(1);
+        for (depth, &n) in self.depths.iter().enumerate() {
+            let bar = (n * 40).div_ceil(widest);
+            writeln!(f, "{:>4} | {:<40} {}", depth, "#".repeat(bar), n)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Violation<T> {
+    RedRoot {
+        value: T,
+        path: Path,
+    },
+    // the node and its parent are both RED
+    RedRed {
+        value: T,
+        path: Path,
+    },
+    BlackHeightMismatch {
+        value: T,
+        path: Path,
+        left: usize,
+        right: usize,
+    },
+    // the node is out of the range its ancestors allow
+    OrderViolation {
+        value: T,
+        path: Path,
+    },
+    // the `len` counter doesn't match the number of nodes
+    LenMismatch {
+        len: usize,
+        counted: usize,
+    },
+    // the deepest node is further than 2*log2(n+1) from the root
+    HeightBoundExceeded {
+        value: T,
+        path: Path,
+        height: usize,
+    },
+    // only for left-leaning trees, a RED node is a right child
+    RightLeaningRed {
+        value: T,
+        path: Path,
+    },
+}
+
+impl<T> Violation<T> {
+    // the offending node if the violation is about a node
+    pub fn node(&self) -> Option<(&T, &Path)> {
+        match self {
+            Violation::RedRoot { value, path }
+            | Violation::RedRed { value, path }
+            | Violation::BlackHeightMismatch { value, path, .. }
+            | Violation::OrderViolation { value, path }
+            | Violation::HeightBoundExceeded { value, path, .. }
+            | Violation::RightLeaningRed { value, path } => Some((value, path)),
+            Violation::LenMismatch { .. } => None,
+        }
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Violation<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::RedRoot { value, .. } => {
+                write!(f, "The root should be BLACK, see val: {}", value)
+            }
+            Violation::RedRed { value, path } => write!(
+                f,
+                "Two consecutive RED nodes, see val: {} at {}",
+                value,
+                PathDisplay(path)
+            ),
+            Violation::BlackHeightMismatch {
+                value,
+                path,
+                left,
+                right,
+            } => write!(
+                f,
+                "Different black heights, see val: {} at {}, left: {} right: {}",
+                value,
+                PathDisplay(path),
+                left,
+                right
+            ),
+            Violation::OrderViolation { value, path } => write!(
+                f,
+                "Wrong order of values, see val: {} at {}",
+                value,
+                PathDisplay(path)
+            ),
+            Violation::LenMismatch { len, counted } => {
+                write!(f, "Length is {} but the tree has {} nodes", len, counted)
+            }
+            Violation::HeightBoundExceeded {
+                value,
+                path,
+                height,
+            } => write!(
+                f,
+                "Height {} is over 2*log2(n+1), see val: {} at {}",
+                height,
+                value,
+                PathDisplay(path)
+            ),
+            Violation::RightLeaningRed { value, path } => write!(
+                f,
+                "RED node is a right child, see val: {} at {}",
+                value,
+                PathDisplay(path)
+            ),
+        }
+    }
+}
+
+// root/L/R
+pub(crate) struct PathDisplay<'a>(pub &'a [Side]);
+
+impl fmt::Display for PathDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "root")?;
+        for side in self.0 {
+            match side {
+                Side::Left => write!(f, "/L")?,
+                Side::Right => write!(f, "/R")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T> RbTree<T>
+where
+    T: std::fmt::Debug + std::cmp::Ord + std::cmp::Eq + std::fmt::Display + Clone,
+{
+    // checks all RbTree rules, the order of values and the length counter
+    pub fn validate(&self) -> Result<TreeStats, Vec<Violation<T>>> {
+        let (stats, violations) = self.inspect(T::clone);
+        if violations.is_empty() {
+            Ok(stats)
+        } else {
+            Err(violations)
+        }
+    }
+
+    // the same as `validate` and no RED right children, which a left-leaning
+    // tree never has, any RbTree could be checked to compare the shapes
+    pub fn validate_llrb(&self) -> Result<TreeStats, Vec<Violation<T>>> {
+        let (stats, mut violations) = self.inspect(T::clone);
+
+        let mut stack: Vec<(Node<T>, Path)> = Vec::new();
+        if let Some(root) = self.root.as_ref() {
+            stack.push((root.clone(), Vec::new()));
+        }
+        while let Some((node, path)) = stack.pop() {
+            let r = node.borrow();
+            for side in [Side::Right, Side::Left] {
+                if let Some(child) = r.children[side.pos()].as_ref() {
+                    let mut path = path.clone();
+                    path.push(side);
+
+                    let c = child.borrow();
+                    if side == Side::Right && c.color == Color::Red {
+                        violations.push(Violation::RightLeaningRed {
+                            value: c.val.clone(),
+                            path: path.clone(),
+                        });
+                    }
+                    stack.push((child.clone(), path));
+                }
+            }
+        }
+
+        if violations.is_empty() {
+            Ok(stats)
+        } else {
+            Err(violations)
+        }
+    }
+}
+
+impl<T> RbTree<T>
+where
+    T: std::fmt::Debug + std::cmp::Ord + std::cmp::Eq + std::fmt::Display,
+{
+    // the same numbers as `validate` gives, even if the tree is broken
+    pub fn stats(&self) -> TreeStats {
+        self.inspect(|_| ()).0
+    }
+
+    pub fn height(&self) -> usize {
+        self.stats().height
+    }
+
+    // counts BLACK nodes on the leftmost path, in a valid tree any path has the same number
+    pub fn black_height(&self) -> usize {
+        let mut height = 0;
+        let mut next = self.root.clone();
+
+        while let Some(node) = next {
+            let r = node.borrow();
+            height += (r.color == Color::Black) as usize;
+            next = r.children[Pos::LEFT].clone();
+        }
+        height
+    }
+
+    // violations keep what `copy` makes of values
+    fn inspect<V>(&self, copy: fn(&T) -> V) -> (TreeStats, Vec<Violation<V>>) {
+        let mut checker = Checker {
+            copy,
+            violations: Vec::new(),
+            path: Vec::new(),
+            counted: 0,
+            red: 0,
+            depths: Vec::new(),
+            deepest: None,
+        };
+
+        let mut black_height = 0;
+        if let Some(root) = self.root.as_ref() {
+            let r = root.borrow();
+            if r.color == Color::Red {
+                checker.violations.push(Violation::RedRoot {
+                    value: copy(&r.val),
+                    path: Vec::new(),
+                });
+            }
+            drop(r);
+            black_height = checker.check(root);
+        }
+
+        if checker.counted != self.len {
+            checker.violations.push(Violation::LenMismatch {
+                len: self.len,
+                counted: checker.counted,
+            });
+        }
+
+        let mut height = 0;
+        if let Some((value, path)) = checker.deepest {
+            height = path.len() + 1;
+            if !height_fits(height, checker.counted) {
+                checker.violations.push(Violation::HeightBoundExceeded {
+                    value,
+                    path,
+                    height,
+                });
+            }
+        }
+
+        // a node is a reference counted RefCell
+        let node = 2 * mem::size_of::<usize>() + mem::size_of::<RefCell<RbTreeNode<T>>>();
+        let memory = mem::size_of::<Self>()
+            + checker.counted * node
+            + self.path.capacity() * mem::size_of::<Ancestor<T>>();
+
+        let stats = TreeStats {
+            len: self.len,
+            height,
+            black_height,
+            red: checker.red,
+            black: checker.counted - checker.red,
+            depths: checker.depths,
+            memory,
+        };
+        (stats, checker.violations)
+    }
+
+    // prints what is broken and why, values are shown with Display,
+    // so it works without `T: Clone`
+    pub(crate) fn is_valid(&self) -> bool {
+        let violations = self.inspect(|v| v.to_string()).1;
+        match self.explanation(&violations) {
+            None => true,
+            Some(explanation) => {
+                print!("{}", explanation);
+                false
+            }
+        }
+    }
+}
+
+// height <= 2*log2(n+1) is the same as 2^height <= (n+1)^2
+fn height_fits(height: usize, len: usize) -> bool {
+    let square = (len as u128 + 1) * (len as u128 + 1);
+    height < 128 && (1u128 << height) <= square
+}
+
+// V is what violations keep of values
+struct Checker<T, V> {
+    copy: fn(&T) -> V,
+    violations: Vec<Violation<V>>,
+    path: Path,
+    counted: usize,
+    red: usize,
+    // nodes on each level
+    depths: Vec<usize>,
+    deepest: Option<(V, Path)>,
+}
+
+enum Visit<T> {
+    Enter {
+        node: Node<T>,
+        // None for the root
+        side: Option<Side>,
+        parent_red: bool,
+        // bounds set by ancestors
+        min: Option<Node<T>>,
+        max: Option<Node<T>>,
+    },
+    // both subtrees are checked
+    Exit {
+        node: Node<T>,
+        side: Option<Side>,
+    },
+}
+
+impl<T: Ord, V> Checker<T, V> {
+    // returns black height of the subtree, if heights are different
+    // the left one is taken to keep checking upper levels
+    //
+    // uses explicit stack instead of recursion, so a broken tree
+    // of any height can't overflow the call stack
+    fn check(&mut self, root: &Node<T>) -> usize {
+        let mut stack = vec![Visit::Enter {
+            node: root.clone(),
+            side: None,
+            parent_red: false,
+            min: None,
+            max: None,
+        }];
+        // black heights of checked subtrees, the right one is on top
+        let mut heights = Vec::new();
+
+        while let Some(visit) = stack.pop() {
+            match visit {
+                Visit::Enter {
+                    node,
+                    side,
+                    parent_red,
+                    min,
+                    max,
+                } => {
+                    let r = node.borrow();
+                    self.counted += 1;
+                    self.red += (r.color == Color::Red) as usize;
+
+                    if let Some(side) = side {
+                        self.path.push(side);
+                        if parent_red && r.color == Color::Red {
+                            self.violations.push(Violation::RedRed {
+                                value: (self.copy)(&r.val),
+                                path: self.path.clone(),
+                            });
+                        }
+                    }
+
+                    let depth = self.path.len();
+                    if self.depths.len() <= depth {
+                        self.depths.push(0);
+                    }
+                    self.depths[depth] += 1;
+
+                    // left subtree is less or equal, right one is greater or equal
+                    if min.as_ref().is_some_and(|m| r.val < m.borrow().val)
+                        || max.as_ref().is_some_and(|m| r.val > m.borrow().val)
+                    {
+                        self.violations.push(Violation::OrderViolation {
+                            value: (self.copy)(&r.val),
+                            path: self.path.clone(),
+                        });
+                    }
+
+                    let is_leaf = r.children.iter().all(|c| c.is_none());
+                    if is_leaf
+                        && self
+                            .deepest
+                            .as_ref()
+                            .is_none_or(|(_, p)| p.len() < self.path.len())
+                    {
+                        self.deepest = Some(((self.copy)(&r.val), self.path.clone()));
+                    }
+
+                    stack.push(Visit::Exit {
+                        node: node.clone(),
+                        side,
+                    });
+                    // the left subtree goes on top to be checked first
+                    for side in [Side::Right, Side::Left] {
+                        if let Some(child) = r.children[side.pos()].as_ref() {
+                            let (min, max) = match side {
+                                Side::Left => (min.clone(), Some(node.clone())),
+                                Side::Right => (Some(node.clone()), max.clone()),
+                            };
+                            stack.push(Visit::Enter {
+                                node: child.clone(),
+                                side: Some(side),
+                                parent_red: r.color == Color::Red,
+                                min,
+                                max,
+                            });
+                        }
+                    }
+                }
+                Visit::Exit { node, side } => {
+                    let r = node.borrow();
+
+                    let mut subtrees = [0; 2];
+                    for pos in [Pos::RIGHT, Pos::LEFT] {
+                        if r.children[pos].is_some() {
+                            subtrees[pos] = heights.pop().unwrap();
+                        }
+                    }
+
+                    if subtrees[Pos::LEFT] != subtrees[Pos::RIGHT] {
+                        self.violations.push(Violation::BlackHeightMismatch {
+                            value: (self.copy)(&r.val),
+                            path: self.path.clone(),
+                            left: subtrees[Pos::LEFT],
+                            right: subtrees[Pos::RIGHT],
+                        });
+                    }
+                    heights.push(subtrees[Pos::LEFT] + (r.color == Color::Black) as usize);
+
+                    if side.is_some() {
+                        self.path.pop();
+                    }
+                }
+            }
+        }
+        heights.pop().unwrap_or(0)
+    }
+}
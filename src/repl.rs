@@ -0,0 +1,315 @@
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    fs,
+    io::{self, BufRead, Write},
+    rc::Rc,
+};
+
+use rbtree::{Encoding, Event, RbTree};
+
+const HELP: &str = "\
+commands:
+  add <v>...        add values
+  remove <v>...     remove values
+  contains <v>      check if the value is in the tree
+  print             print the tree
+  validate          check all RbTree rules
+  stats             show height, colors, depths and memory
+  clear             remove all values
+  undo              revert the last change, up to 100 are kept
+  load <file>       replace the tree, binary `.rbt` files or values separated by spaces
+  save <file>       write `.rbt` binary file or values separated by spaces
+  trace on|off      name rebalancing cases while they happen
+  help              show this message
+  quit              exit
+";
+
+// changes `undo` could revert, older ones are forgotten
+const MAX_HISTORY: usize = 100;
+
+pub struct Repl {
+    tree: RbTree<i32>,
+    // trees before each change, the last one is the most recent
+    history: VecDeque<RbTree<i32>>,
+    trace: bool,
+    // events written by the observer, they go to the output after every step
+    traced: Rc<RefCell<Vec<String>>>,
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        Repl {
+            tree: RbTree::new(),
+            history: VecDeque::new(),
+            trace: false,
+            traced: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+
+    pub fn run<R: BufRead, W: Write>(&mut self, input: R, mut out: W) -> io::Result<()> {
+        write!(out, "> ")?;
+        out.flush()?;
+
+        for line in input.lines() {
+            if !self.execute(&line?, &mut out)? {
+                break;
+            }
+            write!(out, "> ")?;
+            out.flush()?;
+        }
+        Ok(())
+    }
+
+    // returns false when it's time to quit
+    pub fn execute<W: Write>(&mut self, line: &str, out: &mut W) -> io::Result<bool> {
+        let mut words = line.split_whitespace();
+        let command = match words.next() {
+            Some(c) => c,
+            None => return Ok(true),
+        };
+        let args: Vec<&str> = words.collect();
+
+        match command {
+            "add" | "remove" => {
+                let values = match parse_values(&args) {
+                    Ok(v) if !v.is_empty() => v,
+                    Ok(_) => return error(out, &format!("{} needs at least one value", command)),
+                    Err(e) => return error(out, &e),
+                };
+                // nothing to undo if no value is removed
+                if command == "add" || values.iter().any(|v| self.tree.contains(v)) {
+                    self.remember();
+                }
+                for v in values {
+                    if command == "add" {
+                        self.tree.add(v);
+                        self.write_trace(out)?;
+                    } else if self.tree.remove(&v) {
+                        self.write_trace(out)?;
+                    } else {
+                        writeln!(out, "{} is not found", v)?;
+                    }
+                }
+                self.print(out)?;
+            }
+            "contains" => match parse_values(&args).as_deref() {
+                Ok([v]) => writeln!(out, "{}", self.tree.contains(v))?,
+                Ok(_) => return error(out, "contains needs exactly one value"),
+                Err(e) => return error(out, e),
+            },
+            "print" => self.print(out)?,
+            "validate" => match self.tree.validate() {
+                Ok(stats) => writeln!(
+                    out,
+                    "valid, len: {} height: {} black height: {}",
+                    stats.len, stats.height, stats.black_height
+                )?,
+                Err(violations) => {
+                    for v in violations {
+                        writeln!(out, "{}", v)?;
+                    }
+                }
+            },
+            "stats" => write!(out, "{}", self.tree.stats())?,
+            "clear" => {
+                if !self.tree.is_empty() {
+                    self.remember();
+                }
+                self.tree.clear();
+                self.print(out)?;
+            }
+            "undo" => match self.history.pop_back() {
+                Some(tree) => {
+                    self.tree = tree;
+                    self.attach_trace();
+                    self.print(out)?;
+                }
+                None => writeln!(out, "nothing to undo")?,
+            },
+            "load" => match args.as_slice() {
+                [path] => match load(path) {
+                    Ok(tree) => {
+                        self.remember();
+                        self.tree = tree;
+                        self.attach_trace();
+                        self.print(out)?;
+                    }
+                    Err(e) => return error(out, &format!("can't load {}: {}", path, e)),
+                },
+                _ => return error(out, "load needs a file name"),
+            },
+            "save" => match args.as_slice() {
+                [path] => match save(&self.tree, path) {
+                    Ok(()) => writeln!(out, "saved {} values to {}", self.tree.len(), path)?,
+                    Err(e) => return error(out, &format!("can't save {}: {}", path, e)),
+                },
+                _ => return error(out, "save needs a file name"),
+            },
+            "trace" => match args.as_slice() {
+                ["on"] => {
+                    self.trace = true;
+                    self.attach_trace();
+                }
+                ["off"] => {
+                    self.trace = false;
+                    self.tree.take_observer();
+                }
+                _ => return error(out, "trace needs on or off"),
+            },
+            "help" => write!(out, "{}", HELP)?,
+            "quit" | "exit" => return Ok(false),
+            _ => return error(out, &format!("unknown command `{}`, try help", command)),
+        }
+        Ok(true)
+    }
+
+    fn remember(&mut self) {
+        if self.history.len() == MAX_HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back(self.tree.clone());
+    }
+
+    // clones and new trees come without an observer
+    fn attach_trace(&mut self) {
+        if self.trace {
+            let traced = self.traced.clone();
+            self.tree.set_observer(Box::new(move |e: &Event<i32>| {
+                // only what changes the tree
+                if !matches!(e, Event::Operation(_) | Event::Compare(_)) {
+                    traced.borrow_mut().push(format!("  {}", e));
+                }
+            }));
+        }
+    }
+
+    fn write_trace<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        for line in self.traced.borrow_mut().drain(..) {
+            writeln!(out, "{}", line)?;
+        }
+        Ok(())
+    }
+
+    fn print<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        if self.tree.is_empty() {
+            return writeln!(out, "(empty)");
+        }
+        write!(out, "{}", self.tree)
+    }
+}
+
+fn error<W: Write>(out: &mut W, message: &str) -> io::Result<bool> {
+    writeln!(out, "error: {}", message)?;
+    Ok(true)
+}
+
+fn parse_values(args: &[&str]) -> Result<Vec<i32>, String> {
+    args.iter()
+        .map(|a| a.parse().map_err(|_| format!("`{}` is not a number", a)))
+        .collect()
+}
+
+fn load(path: &str) -> Result<RbTree<i32>, String> {
+    let bytes = fs::read(path).map_err(|e| e.to_string())?;
+    if bytes.starts_with(b"RBTR") {
+        return RbTree::from_bytes(&bytes).map_err(|e| e.to_string());
+    }
+
+    let text = String::from_utf8(bytes).map_err(|_| "the file is not a text".to_string())?;
+    let args: Vec<&str> = text.split_whitespace().collect();
+    let mut tree = RbTree::new();
+    for v in parse_values(&args)? {
+        tree.add(v);
+    }
+    Ok(tree)
+}
+
+fn save(tree: &RbTree<i32>, path: &str) -> io::Result<()> {
+    if path.ends_with(".rbt") {
+        return tree.save(path, Encoding::Delta);
+    }
+
+    let mut text = String::new();
+    tree.for_each(|v| {
+        text += &v.to_string();
+        text.push('\n');
+    });
+    fs::write(path, text)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Repl, MAX_HISTORY};
+
+    fn session(repl: &mut Repl, input: &str) -> String {
+        let mut out = Vec::new();
+        repl.run(input.as_bytes(), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_repl() {
+        let mut repl = Repl::new();
+        let out = session(&mut repl, "add 5 3 8\ncontains 3\nremove 3 4\n");
+        assert_eq!(
+            out,
+            "> └─5b\n  ├─8r\n  └─3r\n> true\n> 4 is not found\n└─5b\n  ├─8r\n> "
+        );
+
+        // the whole command is one step back
+        let out = session(&mut repl, "undo\nundo\nundo\n");
+        assert_eq!(
+            out,
+            "> └─5b\n  ├─8r\n  └─3r\n> (empty)\n> nothing to undo\n> "
+        );
+
+        let out = session(&mut repl, "add\nadd x\ntrace maybe\nfly\nquit\nadd 1\n");
+        assert_eq!(
+            out,
+            "> error: add needs at least one value\n\
+             > error: `x` is not a number\n\
+             > error: trace needs on or off\n\
+             > error: unknown command `fly`, try help\n\
+             > "
+        );
+
+        let dir = std::env::temp_dir();
+        for name in ["repl_test.txt", "repl_test.rbt"] {
+            let path = dir.join(name);
+            let path = path.to_str().unwrap();
+
+            session(&mut repl, &format!("add 1 2 3 4\nsave {}\nclear\n", path));
+            let out = session(&mut repl, &format!("load {}\nvalidate\n", path));
+            assert!(out.ends_with("> valid, len: 4 height: 3 black height: 2\n> "));
+            std::fs::remove_file(path).unwrap();
+
+            session(&mut repl, "clear\n");
+        }
+
+        // trace lines go to the same output, right after the value which caused them
+        let mut repl = Repl::new();
+        let out = session(&mut repl, "trace on\nadd 1 2 3\nremove 9\nundo\n");
+        assert_eq!(
+            out,
+            "> > \
+             \x20 insert case 2: uncle is BLACK, node and parent are on the same side, rotate grandparent\n\
+             \x20 recolor 2 Red -> Black\n\
+             \x20 recolor 1 Black -> Red\n\
+             \x20 rotate Left with pivot 2\n\
+             └─2b\n  ├─3r\n  └─1r\n\
+             > 9 is not found\n└─2b\n  ├─3r\n  └─1r\n\
+             > (empty)\n> "
+        );
+
+        // a removal of nothing isn't a change, and only the last changes are kept
+        let adds: String = (0..MAX_HISTORY + 1)
+            .map(|v| format!("add {}\n", v))
+            .collect();
+        session(&mut repl, &format!("trace off\n{}remove 1000\n", adds));
+        let out = session(&mut repl, "undo\ncontains 100\ncontains 99\n");
+        assert!(out.ends_with("> false\n> true\n> "));
+        let out = session(&mut repl, &"undo\n".repeat(MAX_HISTORY));
+        assert!(out.ends_with("> └─0b\n> nothing to undo\n> "));
+    }
+}